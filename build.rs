@@ -0,0 +1,19 @@
+#[cfg(feature = "ffi-header")]
+extern crate cbindgen;
+
+fn main() {
+    #[cfg(feature = "ffi-header")]
+    generate_header();
+}
+
+#[cfg(feature = "ffi-header")]
+fn generate_header() {
+    let crate_dir = ::std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate C header")
+        .write_to_file(format!("{}/include/word_search.h", crate_dir));
+}
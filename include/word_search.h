@@ -0,0 +1,120 @@
+#ifndef WORD_SEARCH_H
+#define WORD_SEARCH_H
+
+/* Generated by cbindgen from src/ffi.rs, rebuild with --features ffi-header to update. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct WsCipher WsCipher;
+
+typedef struct WsIndex WsIndex;
+
+typedef struct WsSolution WsSolution;
+
+typedef struct WsVocabulary WsVocabulary;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Reads a vocabulary file with a word per line, NULL if it can't be read.
+ */
+struct WsVocabulary *ws_vocabulary_load(const char *path);
+
+/**
+ * Same as `ws_vocabulary_load`, but leaves out words shorter than
+ * `min_length`, longer than `max_length`, matching the regular expression
+ * `exclude`, listed in the file `blacklist` or with a known frequency below
+ * `min_frequency`, like the filter flags of the command line tool. 0 or NULL
+ * leaves a rule out, NULL is returned if `exclude` or `blacklist` is invalid.
+ */
+struct WsVocabulary *ws_vocabulary_load_filtered(const char *path,
+                                                 uintptr_t min_length,
+                                                 uintptr_t max_length,
+                                                 const char *exclude,
+                                                 const char *blacklist,
+                                                 uint64_t min_frequency);
+
+/**
+ * Vocabulary from words separated by new lines.
+ */
+struct WsVocabulary *ws_vocabulary_from_text(const char *text);
+
+void ws_vocabulary_free(struct WsVocabulary *vocabulary);
+
+/**
+ * Indexes `vocabulary`, which may be freed afterwards.
+ */
+struct WsIndex *ws_index_new(const struct WsVocabulary *vocabulary);
+
+void ws_index_free(struct WsIndex *index);
+
+/**
+ * Cipher text with optional hints like `"q=e r=t"`, NULL if a hint is
+ * invalid or the text has too many or too long words.
+ */
+struct WsCipher *ws_cipher_new(const char *text, const char *hints);
+
+void ws_cipher_free(struct WsCipher *cipher);
+
+/**
+ * Runs `Decipher::find_solution` and ranks the partial solutions, best first.
+ */
+struct WsSolution *ws_solve(const struct WsIndex *index, const struct WsCipher *cipher);
+
+void ws_solution_free(struct WsSolution *solution);
+
+/**
+ * Number of partial solutions.
+ */
+uintptr_t ws_solution_len(const struct WsSolution *solution);
+
+/**
+ * True when the search tried every letter, false when it stopped at the
+ * solution limit and more partial solutions may exist.
+ */
+bool ws_solution_is_complete(const struct WsSolution *solution);
+
+/**
+ * Number of cipher words, the same for every partial solution.
+ */
+uintptr_t ws_solution_word_count(const struct WsSolution *solution);
+
+/**
+ * Number of candidates of cipher word `word` in partial solution `index`,
+ * 0 when the word is unconstrained and may be any word of its length.
+ */
+uintptr_t ws_solution_candidates_len(const struct WsSolution *solution,
+                                     uintptr_t index,
+                                     uintptr_t word);
+
+/**
+ * Candidate `candidate` of cipher word `word` in partial solution `index`,
+ * NULL when out of range.
+ */
+char *ws_solution_candidate(const struct WsSolution *solution,
+                            uintptr_t index,
+                            uintptr_t word,
+                            uintptr_t candidate);
+
+/**
+ * Text rendering of all partial solutions, as printed by the command line tool.
+ */
+char *ws_solution_render(const struct WsSolution *solution);
+
+/**
+ * JSON rendering of all partial solutions, see `Render::json`.
+ */
+char *ws_solution_json(const struct WsSolution *solution);
+
+void ws_string_free(char *string);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* WORD_SEARCH_H */
@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use json::Json;
+use vocabulary_index::VocabularyIndex;
+
+const ALPHABET_LENGTH: u8 = 26;
+/// Smallest score of a key accepted by `detect`.
+const DETECTION_SCORE: f64 = 0.8;
+/// Multipliers coprime with the alphabet length.
+const MULTIPLIERS: [u8; 12] = [1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25];
+
+/// Affine cipher key: plain letter `x` encrypts to `(a * x + b) mod 26`.
+/// A shift (Caesar) cipher is an affine cipher with `a = 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AffineKey {
+    a: u8,
+    b: u8,
+}
+
+/// Decryption of a cipher text with one key, scored by vocabulary hits.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub key: AffineKey,
+    pub plain_text: String,
+    /// Part of the letters of the text in words found in the vocabulary.
+    pub score: f64,
+}
+
+impl AffineKey {
+    /// None when `a` is not coprime with 26, such a key can't be decrypted.
+    pub fn new(a: u8, b: u8) -> Option<AffineKey> {
+        if MULTIPLIERS.contains(&(a % ALPHABET_LENGTH)) {
+            Some(AffineKey {
+                a: a % ALPHABET_LENGTH,
+                b: b % ALPHABET_LENGTH,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn shift(b: u8) -> AffineKey {
+        AffineKey {
+            a: 1,
+            b: b % ALPHABET_LENGTH,
+        }
+    }
+
+    pub fn is_shift(&self) -> bool {
+        self.a == 1
+    }
+
+    /// All 26 shift keys.
+    pub fn shifts() -> Vec<AffineKey> {
+        (0..ALPHABET_LENGTH).map(AffineKey::shift).collect()
+    }
+
+    /// All 312 affine keys, shifts included.
+    pub fn all() -> Vec<AffineKey> {
+        MULTIPLIERS
+            .iter()
+            .flat_map(|&a| (0..ALPHABET_LENGTH).map(move |b| AffineKey { a, b }))
+            .collect()
+    }
+
+    pub fn decrypt(&self, text: &str) -> String {
+        let a_inverse = (1..ALPHABET_LENGTH)
+            .find(|inverse| u32::from(self.a) * u32::from(*inverse) % u32::from(ALPHABET_LENGTH) == 1)
+            .unwrap();
+        text.chars()
+            .map(|ch| {
+                if ch.is_ascii_lowercase() {
+                    let y = ch as u8 - b'a';
+                    let x = u32::from(a_inverse) * u32::from(y + ALPHABET_LENGTH - self.b) % u32::from(ALPHABET_LENGTH);
+                    (x as u8 + b'a') as char
+                } else {
+                    ch
+                }
+            })
+            .collect()
+    }
+}
+
+/// Decrypts `text` with every key in `keys`, the best scored first.
+pub fn solve(index: &VocabularyIndex, text: &str, keys: &[AffineKey]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = keys
+        .iter()
+        .map(|key| {
+            let plain_text = key.decrypt(text);
+            Candidate {
+                key: *key,
+                score: score(index, &plain_text),
+                plain_text,
+            }
+        })
+        .collect();
+    candidates.sort_by(|left, right| right.score.partial_cmp(&left.score).unwrap_or(Ordering::Equal));
+    candidates
+}
+
+/// Tries the shift keys and then the other affine keys, returns the best
+/// decryption if it reads as vocabulary words well enough.
+pub fn detect(index: &VocabularyIndex, text: &str) -> Option<Candidate> {
+    let accepted = |candidates: Vec<Candidate>| {
+        candidates
+            .into_iter()
+            .next()
+            .filter(|best| best.score >= DETECTION_SCORE)
+    };
+    accepted(solve(index, text, &AffineKey::shifts())).or_else(|| accepted(solve(index, text, &AffineKey::all())))
+}
+
+/// Part of the letters of `plain_text` which are in vocabulary words.
+pub fn score(index: &VocabularyIndex, plain_text: &str) -> f64 {
+    let (found, total) = plain_text
+        .split_whitespace()
+        .fold((0, 0), |(found, total), word| {
+            if index.contains(word) {
+                (found + word.len(), total + word.len())
+            } else {
+                (found, total + word.len())
+            }
+        });
+    if total == 0 {
+        0.
+    } else {
+        found as f64 / total as f64
+    }
+}
+
+impl Candidate {
+    /// `{"a": 3, "b": 7, "shift": false, "plain_text": "...", "score": 0.9}`
+    pub fn json(&self) -> Json {
+        Json::object(vec![
+            ("a", usize::from(self.key.a).into()),
+            ("b", usize::from(self.key.b).into()),
+            ("shift", self.key.is_shift().into()),
+            ("plain_text", self.plain_text.as_str().into()),
+            ("score", self.score.into()),
+        ])
+    }
+}
+
+impl fmt::Display for AffineKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.is_shift() {
+            write!(f, "shift {}", self.b)
+        } else {
+            write!(f, "a={} b={}", self.a, self.b)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use vocabulary::Vocabulary;
+
+    fn index() -> VocabularyIndex {
+        VocabularyIndex::new(Arc::new(Vocabulary::new("the\ncat\nsat\non\nmat".to_string())))
+    }
+
+    fn encrypt(key: AffineKey, text: &str) -> String {
+        text.chars()
+            .map(|ch| {
+                if ch.is_ascii_lowercase() {
+                    let x = ch as u8 - b'a';
+                    (((u32::from(key.a) * u32::from(x) + u32::from(key.b)) % u32::from(ALPHABET_LENGTH)) as u8 + b'a') as char
+                } else {
+                    ch
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_only_invertible_keys() {
+        assert_eq!(AffineKey::new(5, 8), Some(AffineKey { a: 5, b: 8 }));
+        assert_eq!(AffineKey::new(31, 34), Some(AffineKey { a: 5, b: 8 }));
+        assert_eq!(AffineKey::new(2, 1), None);
+        assert_eq!(AffineKey::new(13, 1), None);
+        assert_eq!(AffineKey::shift(29), AffineKey { a: 1, b: 3 });
+        assert_eq!(AffineKey::shifts().len(), 26);
+        assert!(AffineKey::shifts().iter().all(AffineKey::is_shift));
+        assert_eq!(AffineKey::all().len(), 312);
+    }
+
+    #[test]
+    fn decrypts_what_was_encrypted() {
+        for key in AffineKey::all() {
+            let cipher_text = encrypt(key, "the quick brown fox, jumps!");
+            assert_eq!(key.decrypt(&cipher_text), "the quick brown fox, jumps!", "{}", key);
+        }
+        assert_eq!(AffineKey::shift(3).decrypt("wkh fdw"), "the cat");
+    }
+
+    #[test]
+    fn scores_letters_in_vocabulary_words() {
+        let index = index();
+        assert_eq!(score(&index, "the cat"), 1.);
+        assert_eq!(score(&index, "the dog"), 0.5);
+        assert_eq!(score(&index, "zzz"), 0.);
+        assert_eq!(score(&index, "  "), 0.);
+    }
+
+    #[test]
+    fn solves_best_key_first() {
+        let index = index();
+        let cipher_text = encrypt(AffineKey::shift(7), "the cat sat on the mat");
+        let candidates = solve(&index, &cipher_text, &AffineKey::shifts());
+        assert_eq!(candidates.len(), 26);
+        assert_eq!(candidates[0].key, AffineKey::shift(7));
+        assert_eq!(candidates[0].plain_text, "the cat sat on the mat");
+        assert_eq!(candidates[0].score, 1.);
+        assert!(candidates.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    }
+
+    #[test]
+    fn detects_shift_and_affine_keys() {
+        let index = index();
+        let shifted = encrypt(AffineKey::shift(13), "the cat sat on the mat");
+        assert_eq!(detect(&index, &shifted).map(|best| best.key), Some(AffineKey::shift(13)));
+
+        let key = AffineKey::new(5, 8).unwrap();
+        let best = detect(&index, &encrypt(key, "the cat sat on the mat")).unwrap();
+        assert_eq!(best.key, key);
+        assert!(!best.key.is_shift());
+
+        assert!(detect(&index, "xqz vjk").is_none());
+    }
+
+    #[test]
+    fn writes_keys() {
+        assert_eq!(AffineKey::shift(3).to_string(), "shift 3");
+        assert_eq!(AffineKey::new(5, 8).unwrap().to_string(), "a=5 b=8");
+        let candidate = Candidate {
+            key: AffineKey::new(5, 8).unwrap(),
+            plain_text: "the cat".to_string(),
+            score: 1.,
+        };
+        assert_eq!(
+            candidate.json().to_string(),
+            r#"{"a":5,"b":8,"shift":false,"plain_text":"the cat","score":1}"#
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// Weight of derived forms relative to the weight of their `.dic` file,
+/// so that stems rank above them.
+pub const DERIVED_WEIGHT: f64 = 0.5;
+
+/// Prefix and suffix rules of a Hunspell `.aff` file. Only `FLAG`, `PFX` and
+/// `SFX` lines are read, continuation flags of affixes are ignored so every
+/// derived form is one affix (or a prefix and a suffix) away from its stem.
+#[derive(Debug, Clone)]
+pub struct Affixes {
+    flag_type: FlagType,
+    rules: HashMap<String, AffixClass>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// Every character is a flag.
+    Char,
+    /// Every two characters are a flag, `FLAG long`.
+    Long,
+    /// Comma separated numbers, `FLAG num`.
+    Number,
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    kind: AffixKind,
+    cross_product: bool,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    strip: String,
+    add: String,
+    condition: Vec<Condition>,
+}
+
+/// One character of a rule condition: `.`, a letter or `[abc]`/`[^abc]`.
+#[derive(Debug, Clone)]
+enum Condition {
+    Any,
+    Letter(char),
+    Class(Vec<char>, bool),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AffixError {
+    InvalidHeader(usize),
+    InvalidRule(usize),
+    UnknownFlagType(usize),
+}
+
+/// Words of a Hunspell `.dic` file split into stems and the forms derived from
+/// them with `affixes`. Forms equal to a stem are only listed as stems.
+pub fn expand<R: BufRead>(dic: R, affixes: &Affixes) -> io::Result<(Vec<String>, Vec<String>)> {
+    let mut stems = Vec::new();
+    let mut derived = Vec::new();
+    for (line_index, line) in dic.lines().enumerate() {
+        let line = line?;
+        // The first line is the number of entries, but it may be missing.
+        if line_index == 0 && line.trim().parse::<usize>().is_ok() {
+            continue;
+        }
+        let entry = match line.split_whitespace().next() {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let (stem, flags) = match entry.find('/') {
+            Some(slash) => (&entry[..slash], affixes.flags(&entry[slash + 1..])),
+            None => (entry, Vec::new()),
+        };
+        stems.push(stem.to_string());
+        derived.extend(affixes.derive(stem, &flags));
+    }
+    let known: HashSet<&String> = stems.iter().collect();
+    let derived = derived.iter().filter(|word| !known.contains(word)).cloned().collect();
+    Ok((stems, derived))
+}
+
+impl Affixes {
+    fn flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_type {
+            FlagType::Char => flags.chars().map(|flag| flag.to_string()).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|flag| flag.iter().collect()).collect()
+            }
+            FlagType::Number => flags.split(',').map(|flag| flag.trim().to_string()).collect(),
+        }
+    }
+
+    /// Forms of `stem` made by the affix classes of `flags`, with prefixes and
+    /// suffixes combined when both classes allow cross products.
+    fn derive(&self, stem: &str, flags: &[String]) -> Vec<String> {
+        let classes: Vec<&AffixClass> = flags.iter().filter_map(|flag| self.rules.get(flag)).collect();
+        let mut derived = Vec::new();
+        for suffixes in classes.iter().filter(|class| class.kind == AffixKind::Suffix) {
+            for suffixed in suffixes.apply(stem) {
+                if suffixes.cross_product {
+                    for prefixes in classes
+                        .iter()
+                        .filter(|class| class.kind == AffixKind::Prefix && class.cross_product)
+                    {
+                        derived.extend(prefixes.apply(&suffixed));
+                    }
+                }
+                derived.push(suffixed);
+            }
+        }
+        for prefixes in classes.iter().filter(|class| class.kind == AffixKind::Prefix) {
+            derived.extend(prefixes.apply(stem));
+        }
+        derived
+    }
+}
+
+impl AffixClass {
+    fn apply(&self, word: &str) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match self.kind {
+                AffixKind::Prefix => rule.prefix(word),
+                AffixKind::Suffix => rule.suffix(word),
+            })
+            .collect()
+    }
+}
+
+impl Rule {
+    fn suffix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if !word.ends_with(&self.strip) || chars.len() < self.condition.len() {
+            return None;
+        }
+        let end = &chars[chars.len() - self.condition.len()..];
+        if !self.condition.iter().zip(end).all(|(condition, &ch)| condition.matches(ch)) {
+            return None;
+        }
+        Some(format!("{}{}", &word[..word.len() - self.strip.len()], self.add))
+    }
+
+    fn prefix(&self, word: &str) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if !word.starts_with(&self.strip) || chars.len() < self.condition.len() {
+            return None;
+        }
+        if !self.condition.iter().zip(&chars).all(|(condition, &ch)| condition.matches(ch)) {
+            return None;
+        }
+        Some(format!("{}{}", self.add, &word[self.strip.len()..]))
+    }
+}
+
+impl Condition {
+    fn matches(&self, ch: char) -> bool {
+        match *self {
+            Condition::Any => true,
+            Condition::Letter(letter) => letter == ch,
+            Condition::Class(ref letters, negated) => letters.contains(&ch) != negated,
+        }
+    }
+}
+
+/// Parses a condition like `[^aeiou]y`, None if a class is not closed.
+fn parse_condition(condition: &str) -> Option<Vec<Condition>> {
+    let mut parsed = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(ch) = chars.next() {
+        parsed.push(match ch {
+            '.' => Condition::Any,
+            '[' => {
+                let mut letters = Vec::new();
+                let mut negated = false;
+                loop {
+                    match chars.next()? {
+                        ']' => break,
+                        '^' if letters.is_empty() && !negated => negated = true,
+                        letter => letters.push(letter),
+                    }
+                }
+                Condition::Class(letters, negated)
+            }
+            letter => Condition::Letter(letter),
+        });
+    }
+    Some(parsed)
+}
+
+impl FromStr for Affixes {
+    type Err = AffixError;
+
+    fn from_str(aff: &str) -> Result<Affixes, AffixError> {
+        let mut affixes = Affixes {
+            flag_type: FlagType::Char,
+            rules: HashMap::new(),
+        };
+        for (line_index, line) in aff.lines().enumerate() {
+            let line_number = line_index + 1;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let kind = match fields.first() {
+                Some(&"FLAG") => {
+                    affixes.flag_type = match fields.get(1) {
+                        Some(&"long") => FlagType::Long,
+                        Some(&"num") => FlagType::Number,
+                        Some(&"UTF-8") => FlagType::Char,
+                        _ => return Err(AffixError::UnknownFlagType(line_number)),
+                    };
+                    continue;
+                }
+                Some(&"PFX") => AffixKind::Prefix,
+                Some(&"SFX") => AffixKind::Suffix,
+                _ => continue,
+            };
+            let flag = fields.get(1).ok_or(AffixError::InvalidHeader(line_number))?.to_string();
+            match affixes.rules.get_mut(&flag) {
+                None => {
+                    let cross_product = match fields.get(2) {
+                        Some(&"Y") => true,
+                        Some(&"N") => false,
+                        _ => return Err(AffixError::InvalidHeader(line_number)),
+                    };
+                    affixes.rules.insert(
+                        flag,
+                        AffixClass {
+                            kind,
+                            cross_product,
+                            rules: Vec::new(),
+                        },
+                    );
+                }
+                Some(class) => {
+                    if fields.len() < 4 || class.kind != kind {
+                        return Err(AffixError::InvalidRule(line_number));
+                    }
+                    let strip = if fields[2] == "0" { "" } else { fields[2] };
+                    let add = fields[3].split('/').next().unwrap();
+                    let add = if add == "0" { "" } else { add };
+                    let condition = parse_condition(fields.get(4).cloned().unwrap_or("."))
+                        .ok_or(AffixError::InvalidRule(line_number))?;
+                    class.rules.push(Rule {
+                        strip: strip.to_string(),
+                        add: add.to_string(),
+                        condition,
+                    });
+                }
+            }
+        }
+        Ok(affixes)
+    }
+}
+
+impl fmt::Display for AffixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            AffixError::InvalidHeader(line) => write!(f, "invalid affix header at line {}", line),
+            AffixError::InvalidRule(line) => write!(f, "invalid affix rule at line {}", line),
+            AffixError::UnknownFlagType(line) => write!(f, "unknown flag type at line {}", line),
+        }
+    }
+}
+
+impl Error for AffixError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_with(aff: &str, dic: &str) -> (Vec<String>, Vec<String>) {
+        let affixes: Affixes = aff.parse().unwrap();
+        let (stems, mut derived) = expand(dic.as_bytes(), &affixes).unwrap();
+        derived.sort();
+        (stems, derived)
+    }
+
+    #[test]
+    fn reads_long_flags() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\nSFX Bb Y 1\nSFX Bb 0 ed .\n";
+        let (stems, derived) = expand_with(aff, "2\nwalk/AaBb\ncat/Aa\n");
+        assert_eq!(stems, vec!["walk", "cat"]);
+        assert_eq!(derived, vec!["cats", "walked", "walks"]);
+    }
+
+    #[test]
+    fn reads_number_flags() {
+        let aff = "FLAG num\nSFX 12 Y 1\nSFX 12 0 s .\nSFX 7 Y 1\nSFX 7 0 ed .\n";
+        let (_, derived) = expand_with(aff, "walk/12,7\ncat/12\n");
+        assert_eq!(derived, vec!["cats", "walked", "walks"]);
+    }
+
+    #[test]
+    fn applies_strip_add_and_condition() {
+        let aff = "SFX S Y 2\nSFX S y ies [^aeiou]y\nSFX S 0 s [aeiou]y\n";
+        let (_, derived) = expand_with(aff, "city/S\nday/S\ncat/S\n");
+        assert_eq!(derived, vec!["cities", "days"]);
+    }
+
+    #[test]
+    fn reads_zero_as_empty_strip_and_add() {
+        let aff = "SFX D Y 1\nSFX D 0 d e\nSFX E Y 1\nSFX E e 0 e\nPFX R N 1\nPFX R 0 re .\n";
+        let (_, derived) = expand_with(aff, "bake/DER\n");
+        assert_eq!(derived, vec!["bak", "baked", "rebake"]);
+    }
+
+    #[test]
+    fn combines_prefixes_and_suffixes_of_cross_products() {
+        let aff = "PFX U Y 1\nPFX U 0 un .\nSFX D Y 1\nSFX D 0 ed .\nPFX N N 1\nPFX N 0 re .\n";
+        let (_, derived) = expand_with(aff, "lock/UDN\n");
+        assert_eq!(derived, vec!["locked", "relock", "unlock", "unlocked"]);
+    }
+
+    #[test]
+    fn lists_forms_equal_to_stems_only_as_stems() {
+        let aff = "SFX S Y 1\nSFX S 0 s .\n";
+        let (stems, derived) = expand_with(aff, "cat/S\ncats\ndog/S\n");
+        assert_eq!(stems, vec!["cat", "cats", "dog"]);
+        assert_eq!(derived, vec!["dogs"]);
+    }
+
+    #[test]
+    fn reports_line_numbers_of_errors() {
+        assert_eq!("SET UTF-8\nFLAG short".parse::<Affixes>().unwrap_err(), AffixError::UnknownFlagType(2));
+        assert_eq!("SFX S X 1".parse::<Affixes>().unwrap_err(), AffixError::InvalidHeader(1));
+        assert_eq!("SFX".parse::<Affixes>().unwrap_err(), AffixError::InvalidHeader(1));
+        assert_eq!("SFX S Y 1\n\nSFX S 0".parse::<Affixes>().unwrap_err(), AffixError::InvalidRule(3));
+        assert_eq!("SFX S Y 1\nSFX S 0 s [ab".parse::<Affixes>().unwrap_err(), AffixError::InvalidRule(2));
+        assert_eq!("SFX S Y 1\nPFX S 0 s .".parse::<Affixes>().unwrap_err(), AffixError::InvalidRule(2));
+    }
+}
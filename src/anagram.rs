@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use vocabulary::{Vocabulary, WordId};
+
+const ALPHABET_LENGTH: usize = 26;
+
+/// Counts are wide enough for any text held in memory.
+type Signature = [u32; ALPHABET_LENGTH];
+
+/// Groups words of `Vocabulary::by_length` by signature, the count of every
+/// letter in the word, which is the same for all anagrams of a word.
+pub struct AnagramIndex {
+    vocabulary: Arc<Vocabulary>,
+    entries: Vec<Entry>,
+    by_signature: HashMap<Signature, usize>,
+}
+
+struct Entry {
+    signature: Signature,
+    length: usize,
+    words: Vec<WordId>,
+}
+
+struct MultiWordSearch<'r> {
+    candidates: Vec<&'r Entry>,
+    max_words: usize,
+    limit: usize,
+    current: Vec<&'r [WordId]>,
+    found: Vec<Vec<&'r [WordId]>>,
+}
+
+impl AnagramIndex {
+    pub fn new(vocabulary: Arc<Vocabulary>) -> AnagramIndex {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut by_signature = HashMap::new();
+        for (length, words) in vocabulary.by_length().iter().enumerate() {
+            for &word_id in words {
+                let signature = signature(vocabulary.get(word_id).unwrap());
+                let entry_index = *by_signature.entry(signature).or_insert_with(|| {
+                    entries.push(Entry {
+                        signature,
+                        length,
+                        words: Vec::new(),
+                    });
+                    entries.len() - 1
+                });
+                entries[entry_index].words.push(word_id);
+            }
+        }
+        AnagramIndex {
+            vocabulary,
+            entries,
+            by_signature,
+        }
+    }
+
+    pub fn vocabulary(&self) -> &Arc<Vocabulary> {
+        &self.vocabulary
+    }
+
+    /// Words made of exactly the letters of `letters`, non-letters are ignored.
+    pub fn anagrams(&self, letters: &str) -> &[WordId] {
+        self.by_signature
+            .get(&signature(letters))
+            .map(|&entry_index| self.entries[entry_index].words.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Words made of some of the letters of `rack`, the longest first.
+    pub fn sub_anagrams(&self, rack: &str) -> Vec<WordId> {
+        let mut entries = self.fitting_entries(&signature(rack));
+        entries.sort_by_key(|entry| ::std::cmp::Reverse(entry.length));
+        entries
+            .into_iter()
+            .flat_map(|entry| entry.words.iter().cloned())
+            .collect()
+    }
+
+    /// Splits all letters of `letters` into at most `max_words` words. Every found
+    /// anagram is a list of word groups, words of a group are anagrams of each
+    /// other and interchangeable. Stops after `limit` anagrams. Nothing is
+    /// found without letters.
+    pub fn multi_word_anagrams(&self, letters: &str, max_words: usize, limit: usize) -> Vec<Vec<&[WordId]>> {
+        let mut remaining = signature(letters);
+        if remaining.iter().all(|count| *count == 0) {
+            return Vec::new();
+        }
+        let mut candidates = self.fitting_entries(&remaining);
+        candidates.sort_by_key(|entry| ::std::cmp::Reverse(entry.length));
+        let mut search = MultiWordSearch {
+            candidates,
+            max_words,
+            limit,
+            current: Vec::with_capacity(max_words),
+            found: Vec::new(),
+        };
+        search.run(&mut remaining, 0);
+        search.found
+    }
+
+    fn fitting_entries(&self, rack: &Signature) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| fits(&entry.signature, rack))
+            .collect()
+    }
+}
+
+impl<'r> MultiWordSearch<'r> {
+    /// Candidates are only taken in non-decreasing order starting from `start`,
+    /// so every combination of words is found once.
+    fn run(&mut self, remaining: &mut Signature, start: usize) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if remaining.iter().all(|count| *count == 0) {
+            self.found.push(self.current.clone());
+            return;
+        }
+        if self.current.len() == self.max_words {
+            return;
+        }
+        for candidate_index in start..self.candidates.len() {
+            let entry = self.candidates[candidate_index];
+            if !fits(&entry.signature, remaining) {
+                continue;
+            }
+            for (count, used) in remaining.iter_mut().zip(entry.signature.iter()) {
+                *count -= used;
+            }
+            self.current.push(&entry.words);
+            self.run(remaining, candidate_index);
+            self.current.pop();
+            for (count, used) in remaining.iter_mut().zip(entry.signature.iter()) {
+                *count += used;
+            }
+        }
+    }
+}
+
+fn signature(letters: &str) -> Signature {
+    let mut signature = [0; ALPHABET_LENGTH];
+    for ch in letters.bytes().filter(u8::is_ascii_lowercase) {
+        signature[(ch - b'a') as usize] += 1;
+    }
+    signature
+}
+
+fn fits(word: &Signature, rack: &Signature) -> bool {
+    word.iter().zip(rack.iter()).all(|(needed, available)| needed <= available)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> AnagramIndex {
+        AnagramIndex::new(Arc::new(Vocabulary::new(
+            "listen\nsilent\nenlist\ntinsel\nten\nnet\nlis\nis\nit\nsit\nlet".to_string(),
+        )))
+    }
+
+    fn words<'r>(index: &'r AnagramIndex, word_ids: &[WordId]) -> Vec<&'r str> {
+        word_ids.iter().map(|word_id| index.vocabulary().get(*word_id).unwrap()).collect()
+    }
+
+    fn sorted<'r>(index: &'r AnagramIndex, word_ids: &[WordId]) -> Vec<&'r str> {
+        let mut words = words(index, word_ids);
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn finds_anagrams() {
+        let index = index();
+        assert_eq!(
+            sorted(&index, index.anagrams("silent!")),
+            vec!["enlist", "listen", "silent", "tinsel"]
+        );
+        assert_eq!(sorted(&index, index.anagrams("tne")), vec!["net", "ten"]);
+        assert!(index.anagrams("xyz").is_empty());
+        assert!(index.anagrams("").is_empty());
+    }
+
+    #[test]
+    fn finds_sub_anagrams_longest_first() {
+        let index = index();
+        let found = words(&index, &index.sub_anagrams("tensi"));
+        let lengths: Vec<usize> = found.iter().map(|word| word.len()).collect();
+        assert_eq!(lengths, vec![3, 3, 3, 2, 2]);
+        assert_eq!(sorted(&index, &index.sub_anagrams("tensi")), vec!["is", "it", "net", "sit", "ten"]);
+        assert!(index.sub_anagrams("").is_empty());
+        assert!(index.sub_anagrams("q").is_empty());
+    }
+
+    #[test]
+    fn splits_letters_into_words() {
+        let index = index();
+        let phrases: Vec<Vec<Vec<&str>>> = index
+            .multi_word_anagrams("netsit", 2, usize::MAX)
+            .into_iter()
+            .map(|phrase| phrase.into_iter().map(|group| sorted(&index, group)).collect())
+            .collect();
+        assert_eq!(phrases, vec![vec![vec!["net", "ten"], vec!["sit"]]]);
+
+        assert_eq!(index.multi_word_anagrams("listen", 1, usize::MAX).len(), 1);
+        assert_eq!(index.multi_word_anagrams("netsit", 1, usize::MAX).len(), 0);
+        assert_eq!(index.multi_word_anagrams("netsit", 2, 0).len(), 0);
+    }
+
+    #[test]
+    fn finds_nothing_without_letters() {
+        let index = index();
+        assert!(index.multi_word_anagrams("", 3, usize::MAX).is_empty());
+        assert!(index.multi_word_anagrams("123 !", 3, usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn counts_many_copies_of_a_letter() {
+        let index = index();
+        let letters = "e".repeat(300);
+        assert_eq!(signature(&letters)[4], 300);
+        assert!(index.anagrams(&letters).is_empty());
+        assert!(index.sub_anagrams(&letters).is_empty());
+        assert!(index.multi_word_anagrams(&letters, 2, 10).is_empty());
+    }
+}
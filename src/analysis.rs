@@ -0,0 +1,166 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use cipher_text::{CipherText, Condition};
+use pattern::Pattern;
+use vocabulary::Vocabulary;
+use vocabulary_index::VocabularyIndex;
+
+/// Most frequent cipher letters for which likely plain letters are guessed.
+const HIGH_FREQUENCY_LETTERS: usize = 6;
+/// Likely plain letters listed for each of them.
+const LIKELY_MAPPINGS: usize = 3;
+
+/// Report on a cipher text to help choosing hints and condition order.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// Cipher letters with their share of all cipher letters, most frequent first.
+    pub letters: Vec<(char, f64)>,
+    /// Plain letters with their share of all letters of vocabulary words,
+    /// most frequent first.
+    pub vocabulary_letters: Vec<(char, f64)>,
+    pub words: Vec<WordAnalysis>,
+    /// Conditions in search order.
+    pub conditions: Vec<Condition>,
+    /// Plain letters the pattern-matching words read the most frequent cipher
+    /// letters as, with the average share of such words.
+    pub likely_mappings: Vec<(char, Vec<(char, f64)>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WordAnalysis {
+    pub cipher_word: String,
+    pub pattern: Pattern,
+    /// Vocabulary words with the same repetition pattern.
+    pub matches: usize,
+}
+
+pub fn analyze(index: &VocabularyIndex, cipher_text: &CipherText) -> Analysis {
+    let cipher_words = cipher_text.words();
+    let matches: Vec<Vec<&str>> = cipher_words
+        .iter()
+        .map(|word| {
+            index
+                .query(&Pattern::repetition(word))
+                .into_iter()
+                .map(|word_id| index.vocabulary().get(word_id).unwrap())
+                .collect()
+        })
+        .collect();
+
+    let letters = letter_shares(cipher_text.text().chars());
+    let mut likely_mappings = Vec::new();
+    for &(cipher_char, _) in letters.iter().take(HIGH_FREQUENCY_LETTERS) {
+        let mut votes: HashMap<char, f64> = HashMap::new();
+        let mut voters = 0;
+        for (cipher_word, words) in cipher_words.iter().zip(&matches) {
+            let position = match cipher_word.find(cipher_char) {
+                Some(position) => position,
+                None => continue,
+            };
+            if words.is_empty() {
+                continue;
+            }
+            voters += 1;
+            for word in words {
+                *votes.entry(word.as_bytes()[position] as char).or_insert(0.) += 1. / words.len() as f64;
+            }
+        }
+        let mut likely: Vec<(char, f64)> = votes
+            .into_iter()
+            .map(|(plain, votes)| (plain, votes / f64::from(voters)))
+            .collect();
+        likely.sort_by(by_share);
+        likely.truncate(LIKELY_MAPPINGS);
+        likely_mappings.push((cipher_char, likely));
+    }
+
+    Analysis {
+        letters,
+        vocabulary_letters: vocabulary_letters(index.vocabulary()),
+        words: cipher_words
+            .iter()
+            .zip(&matches)
+            .map(|(cipher_word, words)| WordAnalysis {
+                cipher_word: cipher_word.to_string(),
+                pattern: Pattern::repetition(cipher_word),
+                matches: words.len(),
+            })
+            .collect(),
+        conditions: cipher_text.conditions().to_vec(),
+        likely_mappings,
+    }
+}
+
+/// Every word counts once, whatever its frequency.
+fn vocabulary_letters(vocabulary: &Vocabulary) -> Vec<(char, f64)> {
+    let words = vocabulary.by_length().iter().flatten();
+    letter_shares(words.flat_map(|word_id| vocabulary.get(*word_id).unwrap().chars()))
+}
+
+pub(crate) fn letter_shares<I: Iterator<Item = char>>(chars: I) -> Vec<(char, f64)> {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for ch in chars.filter(char::is_ascii_lowercase) {
+        *counts.entry(ch).or_insert(0) += 1;
+    }
+    let total: usize = counts.values().sum();
+    let mut shares: Vec<(char, f64)> = counts
+        .into_iter()
+        .map(|(ch, count)| (ch, count as f64 / total as f64))
+        .collect();
+    shares.sort_by(by_share);
+    shares
+}
+
+/// Higher share first, then alphabetically.
+fn by_share(left: &(char, f64), right: &(char, f64)) -> Ordering {
+    right.1
+        .partial_cmp(&left.1)
+        .unwrap_or(Ordering::Equal)
+        .then(left.0.cmp(&right.0))
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(f, "Letter frequencies (cipher / vocabulary):")?;
+        let rows = self.letters.len().max(self.vocabulary_letters.len());
+        for row in 0..rows {
+            match self.letters.get(row) {
+                Some(&(ch, share)) => write!(f, "    {} {:5.1}%", ch, share * 100.)?,
+                None => write!(f, "{:12}", "")?,
+            }
+            if let Some(&(ch, share)) = self.vocabulary_letters.get(row) {
+                write!(f, "    {} {:5.1}%", ch, share * 100.)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Words (pattern, matching vocabulary words):")?;
+        for word in &self.words {
+            writeln!(f, "    {:<12} {:<12} {}", word.cipher_word, word.pattern.to_string(), word.matches)?;
+        }
+
+        writeln!(f, "Conditions (cipher letter, score):")?;
+        for (index, condition) in self.conditions.iter().enumerate() {
+            writeln!(
+                f,
+                "    {}) {} {:3} {}",
+                index + 1,
+                condition.cipher_char(),
+                condition.score(),
+                condition
+            )?;
+        }
+
+        writeln!(f, "Likely mappings:")?;
+        for &(cipher_char, ref likely) in &self.likely_mappings {
+            write!(f, "    {} =", cipher_char)?;
+            for &(plain, share) in likely {
+                write!(f, " {} {:.0}%", plain, share * 100.)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,395 @@
+use std::str::FromStr;
+
+use structopt::clap::{AppSettings, Arg, ArgMatches};
+use word_search::cipher_text::Hint;
+use word_search::key::KeyedAlphabet;
+use word_search::pattern::Pattern;
+use word_search::render::Layout;
+use word_search::trace::TraceFormat;
+
 #[derive(StructOpt, Debug)]
-#[structopt(name = "CryptoFind", about = "Finds words for 'Cryptogram'")]
+#[structopt(name = "CryptoFind", about = "Finds words for 'Cryptogram'",
+            settings_raw = "&[AppSettings::SubcommandsNegateReqs, AppSettings::ArgsNegateSubcommands]")]
 pub struct App {
-    #[structopt(help = "Vocabulary file")]
-    pub vocabulary: String,
+    #[structopt(help = "Vocabulary file as FILE[:WEIGHT], Hunspell FILE.dic with FILE.aff next to it, \
+                        or vocabulary cache, any of them may be gzipped")]
+    pub vocabulary: Option<SourceSpec>,
 
     #[structopt(help = "List of patterns")]
-    pub chipher_text: String,
+    pub chipher_text: Option<String>,
+
+    #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+    pub extra_vocabularies: Vec<SourceSpec>,
+
+    #[structopt(long = "hint", help = "Known letter as CIPHER=PLAIN, e.g. q=e", number_of_values_raw = "1")]
+    pub hints: Vec<Hint>,
+
+    #[structopt(long = "no-self-mapping", help = "No letter encrypts to itself, as in Aristocrat puzzles")]
+    pub no_self_mapping: bool,
+
+    #[structopt(long = "progress", help = "Show search progress bar")]
+    pub progress: bool,
+
+    #[structopt(long = "threads", help = "Search in parallel on given number of threads, 0 for all cores")]
+    pub threads: Option<usize>,
+
+    #[structopt(long = "trace", help = "Write every step of the search to a file, the search runs on one thread")]
+    pub trace: Option<String>,
+
+    #[structopt(long = "trace-format", help = "Trace file format: log or json (one object per line)",
+                default_value = "log")]
+    pub trace_format: TraceFormat,
+
+    #[structopt(long = "format", help = "Output format: text or json, json skips the reorder prompt",
+                default_value = "text")]
+    pub format: OutputFormat,
+
+    #[structopt(long = "layout", help = "Text layout: aligned (plain letters under cipher text), columns, \
+                                        compact (line per cipher word) or expanded (every consistent combination)",
+                default_value = "aligned")]
+    pub layout: Layout,
+
+    #[structopt(long = "color", help = "Color the aligned layout: never, auto (on a terminal unless NO_COLOR is \
+                                       set) or always", default_value = "auto")]
+    pub color: ColorMode,
+
+    #[structopt(long = "show", help = "Candidates per cipher word (compact) or combinations per solution \
+                                      (expanded) to show")]
+    pub show: Option<usize>,
+
+    #[structopt(long = "mode", help = "Cipher to solve: substitution, shift, affine or auto (shift and affine \
+                                      before substitution)", default_value = "substitution")]
+    pub mode: Mode,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(name = "build-cache", about = "Saves vocabulary with its index for fast loading")]
+    BuildCache {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or Hunspell FILE.dic with FILE.aff next to it")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cache file to write")]
+        output: String,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+    },
+
+    #[structopt(name = "query", about = "Lists words matching a pattern like 'c?a?e', '[aeiou]??' or 'A?BA'")]
+    Query {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Pattern: a-z known letter, ? any letter, [abc] or [^abc] letter class, A-Z variable")]
+        pattern: Pattern,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "limit", help = "Print at most this many words")]
+        limit: Option<usize>,
+    },
+
+    #[structopt(name = "anagram", about = "Finds anagrams of letters")]
+    Anagram {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Letters to rearrange")]
+        letters: String,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "sub", help = "Find words made of some of the letters")]
+        sub: bool,
+
+        #[structopt(long = "words", help = "Find phrases of up to this many words using all letters")]
+        words: Option<usize>,
+
+        #[structopt(long = "limit", help = "Print at most this many results")]
+        limit: Option<usize>,
+    },
+
+    #[structopt(name = "encrypt", about = "Makes a cryptogram of plain text")]
+    Encrypt {
+        #[structopt(help = "Plain text")]
+        text: String,
+
+        #[structopt(long = "keyword", help = "Derive the key from a keyword instead of choosing it randomly")]
+        keyword: Option<String>,
+
+        #[structopt(long = "alphabet", help = "Keyed alphabet for --keyword, k1, k2 or k3", default_value = "k1")]
+        alphabet: KeyedAlphabet,
+
+        #[structopt(long = "shift", help = "Shift of the keyed alphabet, by default the first one without self-mapped letters")]
+        shift: Option<usize>,
+
+        #[structopt(long = "seed", help = "Seed for the random key")]
+        seed: Option<u64>,
+
+        #[structopt(long = "print-key", help = "Print the key after the cryptogram")]
+        print_key: bool,
+    },
+
+    #[structopt(name = "verify", about = "Checks that a cryptogram has exactly one solution")]
+    Verify {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cipher text")]
+        chipher_text: String,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "hint", help = "Known letter as CIPHER=PLAIN, e.g. q=e", number_of_values_raw = "1")]
+        hints: Vec<Hint>,
+
+        #[structopt(long = "plain", help = "Intended plain text, adds hints until it is the only solution")]
+        plain: Option<String>,
+    },
+
+    #[structopt(name = "rate", about = "Rates difficulty of cryptograms, easiest first")]
+    Rate {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cipher texts")]
+        chipher_texts: Vec<String>,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+    },
+
+    #[structopt(name = "serve", about = "Answers solve, query and stats requests over HTTP with JSON")]
+    Serve {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "address", help = "Address to listen on", default_value = "127.0.0.1:8080")]
+        address: String,
+
+        #[structopt(long = "time-limit", help = "Longest time spent on a solve request in milliseconds",
+                    default_value = "10000")]
+        time_limit: usize,
+
+        #[structopt(long = "max-solutions", help = "Most solutions returned for a request", default_value = "1000")]
+        max_solutions: usize,
+    },
+
+    #[structopt(name = "keyword", about = "Recovers the keyword of a K1, K2 or K3 key from known letters")]
+    Keyword {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cipher text, its best solution adds known letters and gets decrypted")]
+        chipher_text: Option<String>,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "hint", help = "Known letter as CIPHER=PLAIN, e.g. q=e", number_of_values_raw = "1")]
+        hints: Vec<Hint>,
+
+        #[structopt(long = "limit", help = "Print at most this many keywords", default_value = "10")]
+        limit: usize,
+    },
+
+    #[structopt(name = "analyze", about = "Reports letter frequencies, word patterns, conditions and likely \
+                                         mappings of a cipher text")]
+    Analyze {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cipher text")]
+        chipher_text: String,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+
+        #[structopt(long = "hint", help = "Known letter as CIPHER=PLAIN, e.g. q=e", number_of_values_raw = "1")]
+        hints: Vec<Hint>,
+    },
+
+    #[structopt(name = "vocab-stats", about = "Reports accepted, rejected and duplicate words, lengths, letters \
+                                             and ambiguous patterns of vocabulary files")]
+    VocabStats {
+        #[structopt(help = "Vocabulary files or Hunspell FILE.dic with FILE.aff next to it, any of them may be \
+                            gzipped")]
+        vocabularies: Vec<String>,
+    },
+
+    #[structopt(name = "repl", about = "Solves a cryptogram interactively")]
+    Repl {
+        #[structopt(help = "Vocabulary file as FILE[:WEIGHT] or vocabulary cache")]
+        vocabulary: SourceSpec,
+
+        #[structopt(help = "Cipher text to start with")]
+        chipher_text: Option<String>,
+
+        #[structopt(long = "with", help = "Additional vocabulary file as FILE[:WEIGHT]", number_of_values_raw = "1")]
+        extra_vocabularies: Vec<SourceSpec>,
+    },
+}
+
+/// Vocabulary filter flags of the solver and of every subcommand loading a
+/// vocabulary. structopt can't share fields between subcommands, so the flags
+/// are global arguments added to `App::clap()` by `args` and read back from
+/// the matches of the command which runs.
+#[derive(Debug, Default)]
+pub struct FilterOptions {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub exclude: Option<String>,
+    pub blacklist: Option<String>,
+    pub min_frequency: Option<u64>,
+}
+
+impl FilterOptions {
+    pub fn args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("min-length")
+                .long("min-length")
+                .takes_value(true)
+                .global(true)
+                .validator(is_number)
+                .help("Leave out vocabulary words shorter than this"),
+            Arg::with_name("max-length")
+                .long("max-length")
+                .takes_value(true)
+                .global(true)
+                .validator(is_number)
+                .help("Leave out vocabulary words longer than this"),
+            Arg::with_name("exclude")
+                .long("exclude")
+                .takes_value(true)
+                .global(true)
+                .help("Leave out vocabulary words matching this regular expression"),
+            Arg::with_name("blacklist")
+                .long("blacklist")
+                .takes_value(true)
+                .global(true)
+                .help("Leave out vocabulary words listed in this file, one per line"),
+            Arg::with_name("min-frequency")
+                .long("min-frequency")
+                .takes_value(true)
+                .global(true)
+                .validator(is_number)
+                .help("Leave out vocabulary words with a known frequency below this"),
+        ]
+    }
+
+    /// Options of the subcommand in `matches`, or of the solver without one.
+    pub fn from_matches(matches: &ArgMatches) -> FilterOptions {
+        let matches = matches.subcommand().1.unwrap_or(matches);
+        let number = |name| matches.value_of(name).map(|value: &str| value.parse().unwrap());
+        FilterOptions {
+            min_length: number("min-length").map(|length: u64| length as usize),
+            max_length: number("max-length").map(|length: u64| length as usize),
+            exclude: matches.value_of("exclude").map(str::to_string),
+            blacklist: matches.value_of("blacklist").map(str::to_string),
+            min_frequency: number("min-frequency"),
+        }
+    }
+}
+
+fn is_number(value: String) -> Result<(), String> {
+    value.parse::<u64>().map(|_| ()).map_err(|err| err.to_string())
+}
+
+/// Vocabulary file name with an optional `:WEIGHT` suffix, weight defaults to 1.
+#[derive(Debug, Clone)]
+pub struct SourceSpec {
+    pub path: String,
+    pub weight: f64,
+}
+
+impl FromStr for SourceSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<SourceSpec, String> {
+        if let Some(colon) = spec.rfind(':') {
+            if let Ok(weight) = spec[colon + 1..].parse::<f64>() {
+                if weight < 0. || !weight.is_finite() {
+                    return Err(format!("invalid weight in {}", spec));
+                }
+                return Ok(SourceSpec {
+                    path: spec[..colon].to_string(),
+                    weight,
+                });
+            }
+        }
+        Ok(SourceSpec {
+            path: spec.to_string(),
+            weight: 1.,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Never,
+    Auto,
+    Always,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<ColorMode, String> {
+        match mode {
+            "never" => Ok(ColorMode::Never),
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            _ => Err(format!("unknown color mode {}, expected never, auto or always", mode)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<OutputFormat, String> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown output format {}, expected text or json", format)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Substitution,
+    Shift,
+    Affine,
+    Auto,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Mode, String> {
+        match mode {
+            "substitution" => Ok(Mode::Substitution),
+            "shift" | "caesar" => Ok(Mode::Shift),
+            "affine" => Ok(Mode::Affine),
+            "auto" => Ok(Mode::Auto),
+            _ => Err(format!("unknown mode {}, expected substitution, shift, affine or auto", mode)),
+        }
+    }
 }
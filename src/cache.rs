@@ -0,0 +1,321 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use vocabulary::Vocabulary;
+use vocabulary_index::VocabularyIndex;
+
+const MAGIC: &[u8; 8] = b"WSCACHE\0";
+const VERSION: u32 = 4;
+const HEADER_LENGTH: usize = 8 + 4 + 8 + 8;
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    NotACache,
+    UnsupportedVersion(u32),
+    ChecksumMismatch,
+    Corrupted,
+    Stale(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct SourceStamp {
+    path: String,
+    length: u64,
+    modified: u64,
+}
+
+pub fn is_cache(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Writes `index` and its vocabulary to `path`, remembering size and modification
+/// time of every file in `sources` so that the cache is rejected once any of them changes.
+pub fn save<P: AsRef<Path>, S: AsRef<Path>>(
+    path: P,
+    sources: &[S],
+    index: &VocabularyIndex,
+) -> Result<(), CacheError> {
+    let vocabulary = index.vocabulary();
+
+    let mut payload = Vec::with_capacity(3 * vocabulary.text().len());
+    put_u32(&mut payload, sources.len() as u32);
+    for source in sources {
+        let stamp = SourceStamp::of(source.as_ref())?;
+        put_bytes(&mut payload, stamp.path.as_bytes());
+        put_u64(&mut payload, stamp.length);
+        put_u64(&mut payload, stamp.modified);
+    }
+    vocabulary.encode(&mut payload);
+    index.encode(&mut payload);
+
+    let mut file = File::create(path)?;
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    header.extend_from_slice(MAGIC);
+    put_u32(&mut header, VERSION);
+    put_u64(&mut header, checksum(&payload));
+    put_u64(&mut header, payload.len() as u64);
+    file.write_all(&header)?;
+    file.write_all(&payload)?;
+    Ok(())
+}
+
+/// Validates and decodes an index with its vocabulary previously written by `save`.
+pub fn load(bytes: &[u8]) -> Result<VocabularyIndex, CacheError> {
+    if !is_cache(bytes) {
+        return Err(CacheError::NotACache);
+    }
+    if bytes.len() < HEADER_LENGTH {
+        return Err(CacheError::Corrupted);
+    }
+    let mut header = &bytes[MAGIC.len()..];
+    let version = take_u32(&mut header).ok_or(CacheError::Corrupted)?;
+    if version != VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+    let expected_checksum = take_u64(&mut header).ok_or(CacheError::Corrupted)?;
+    let payload_length = take_u64(&mut header).ok_or(CacheError::Corrupted)? as usize;
+    let payload = &bytes[HEADER_LENGTH..];
+    if payload.len() != payload_length || checksum(payload) != expected_checksum {
+        return Err(CacheError::ChecksumMismatch);
+    }
+
+    let mut payload = payload;
+    let source_count = take_u32(&mut payload).ok_or(CacheError::Corrupted)?;
+    for _ in 0..source_count {
+        let stamp = SourceStamp {
+            path: take_string(&mut payload).ok_or(CacheError::Corrupted)?,
+            length: take_u64(&mut payload).ok_or(CacheError::Corrupted)?,
+            modified: take_u64(&mut payload).ok_or(CacheError::Corrupted)?,
+        };
+        if let Ok(current) = SourceStamp::of(Path::new(&stamp.path)) {
+            if current != stamp {
+                return Err(CacheError::Stale(stamp.path));
+            }
+        }
+    }
+    let vocabulary = Arc::new(Vocabulary::decode(&mut payload).ok_or(CacheError::Corrupted)?);
+    let index = VocabularyIndex::decode(vocabulary, &mut payload).ok_or(CacheError::Corrupted)?;
+    if !payload.is_empty() {
+        return Err(CacheError::Corrupted);
+    }
+
+    Ok(index)
+}
+
+impl SourceStamp {
+    fn of(source: &Path) -> Result<SourceStamp, CacheError> {
+        let metadata = fs::metadata(source)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Ok(SourceStamp {
+            path: fs::canonicalize(source)?.to_string_lossy().into_owned(),
+            length: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// FNV-1a, good enough to notice truncated or damaged files.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub(crate) fn put_u8(out: &mut Vec<u8>, value: u8) {
+    out.push(value);
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u64(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn take<'a>(input: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+    if input.len() < length {
+        return None;
+    }
+    let (taken, rest) = input.split_at(length);
+    *input = rest;
+    Some(taken)
+}
+
+pub(crate) fn take_u8(input: &mut &[u8]) -> Option<u8> {
+    take(input, 1).map(|bytes| bytes[0])
+}
+
+pub(crate) fn take_u32(input: &mut &[u8]) -> Option<u32> {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(take(input, 4)?);
+    Some(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn take_u64(input: &mut &[u8]) -> Option<u64> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(take(input, 8)?);
+    Some(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn take_string(input: &mut &[u8]) -> Option<String> {
+    let length = take_u64(input)? as usize;
+    String::from_utf8(take(input, length)?.to_vec()).ok()
+}
+
+impl From<io::Error> for CacheError {
+    fn from(err: io::Error) -> CacheError {
+        CacheError::Io(err)
+    }
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            CacheError::Io(ref err) => write!(f, "i/o error: {}", err),
+            CacheError::NotACache => write!(f, "not a vocabulary cache"),
+            CacheError::UnsupportedVersion(version) => write!(
+                f,
+                "cache version {} is not supported, expected {}",
+                version, VERSION
+            ),
+            CacheError::ChecksumMismatch => write!(f, "cache checksum mismatch"),
+            CacheError::Corrupted => write!(f, "cache is corrupted"),
+            CacheError::Stale(ref source) => write!(f, "cache is stale, {} has changed", source),
+        }
+    }
+}
+
+impl Error for CacheError {}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::*;
+    use pattern::Pattern;
+    use vocabulary::{Filter, Source};
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("word-search-{}-{}", process::id(), name))
+    }
+
+    fn index() -> VocabularyIndex {
+        let sources = vec![
+            (Source::new("common".to_string(), 2.), "cat 120\ndog\n".to_string()),
+            (Source::new("rare".to_string(), 0.5), "cat 300\ncow\n".to_string()),
+        ];
+        let filter = Filter {
+            max_length: Some(3),
+            ..Filter::default()
+        };
+        VocabularyIndex::new(Arc::new(Vocabulary::merge_filtered(sources, filter)))
+    }
+
+    fn saved(name: &str, sources: &[PathBuf]) -> Vec<u8> {
+        let path = temp_path(name);
+        save(&path, sources, &index()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    fn words(index: &VocabularyIndex, pattern: &str) -> Vec<String> {
+        let vocabulary = index.vocabulary();
+        let mut words: Vec<String> = index
+            .query(&pattern.parse::<Pattern>().unwrap())
+            .into_iter()
+            .map(|word_id| vocabulary.get(word_id).unwrap().to_string())
+            .collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn loads_what_was_saved() {
+        let bytes = saved("round-trip", &[]);
+        assert!(is_cache(&bytes));
+        let loaded = load(&bytes).unwrap();
+        let original = index();
+        assert_eq!(words(&loaded, "???"), words(&original, "???"));
+        assert_eq!(words(&loaded, "c?w"), vec!["cow"]);
+
+        let vocabulary = loaded.vocabulary();
+        let names: Vec<&str> = vocabulary.sources().iter().map(Source::name).collect();
+        assert_eq!(names, vec!["common", "rare"]);
+        let cat = loaded.query(&"cat".parse().unwrap())[0];
+        assert_eq!(vocabulary.frequency(cat), Some(300));
+        assert_eq!(vocabulary.weight(cat), 2.);
+        assert_eq!(vocabulary.filter(), original.vocabulary().filter());
+    }
+
+    #[test]
+    fn rejects_damaged_caches() {
+        let bytes = saved("damaged", &[]);
+        assert!(matches!(load(b"cat\ndog\n"), Err(CacheError::NotACache)));
+        assert!(matches!(load(&bytes[..HEADER_LENGTH - 1]), Err(CacheError::Corrupted)));
+        assert!(matches!(load(&bytes[..bytes.len() - 1]), Err(CacheError::ChecksumMismatch)));
+
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 1;
+        assert!(matches!(load(&flipped), Err(CacheError::ChecksumMismatch)));
+
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(load(&newer), Err(CacheError::UnsupportedVersion(version)) if version == VERSION + 1));
+    }
+
+    #[test]
+    fn rejects_caches_of_changed_sources() {
+        let source = temp_path("source.txt");
+        fs::write(&source, "cat\ndog\n").unwrap();
+        let bytes = saved("stale", std::slice::from_ref(&source));
+        assert!(load(&bytes).is_ok());
+
+        fs::write(&source, "cat\ndog\ncow\n").unwrap();
+        let stale = load(&bytes);
+        fs::remove_file(&source).unwrap();
+        match stale {
+            Err(CacheError::Stale(path)) => assert!(path.ends_with("source.txt")),
+            other => panic!("expected a stale cache, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn takes_what_was_put() {
+        let mut out = Vec::new();
+        put_u8(&mut out, 7);
+        put_u32(&mut out, 70_000);
+        put_u64(&mut out, u64::MAX);
+        put_bytes(&mut out, "word".as_bytes());
+
+        let mut input = &out[..];
+        assert_eq!(take_u8(&mut input), Some(7));
+        assert_eq!(take_u32(&mut input), Some(70_000));
+        assert_eq!(take_u64(&mut input), Some(u64::MAX));
+        assert_eq!(take_string(&mut input), Some("word".to_string()));
+        assert!(input.is_empty());
+        assert_eq!(take_u32(&mut input), None);
+
+        let mut short = &out[..3];
+        assert_eq!(take_u64(&mut short), None);
+    }
+}
@@ -1,14 +1,30 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
-use vocabulary::Position;
+use vocabulary::{AlphabetIter, Char, Position};
 
-#[derive(Debug)]
+/// Most words of a cipher text, word ids are stored in a byte.
+pub const MAX_WORDS: usize = 256;
+/// Longest word of a cipher text, positions are stored in a byte.
+pub const MAX_WORD_LENGTH: usize = 255;
+
+#[derive(Debug, Clone)]
 pub struct CipherText {
     text: String,
-    word_count: usize,
     conditions: Vec<Condition>,
     lengths: Vec<usize>,
+    hints: Vec<Hint>,
+    no_self_mapping: bool,
+}
+
+/// Known plain letter of a cipher letter, written as `q=e`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub cipher: char,
+    pub plain: Char,
 }
 
 #[derive(Clone, Copy)]
@@ -23,23 +39,90 @@ pub struct CipherWordId(u8);
 
 #[derive(Clone)]
 pub struct Condition {
+    cipher_char: char,
     equal_chars: Vec<CipherChar>,
+    hint: Option<Char>,
+    no_self_mapping: bool,
+}
+
+/// Why a text can't be used as a cipher text, see `CipherText::try_with_hints`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CipherTextError {
+    TooManyWords(usize),
+    WordTooLong(String),
+    HintNotInText(Hint),
+    /// Two hints with different plain letters for one cipher letter.
+    ConflictingHints(Hint, Hint),
+    /// Two hints with the same plain letter for different cipher letters.
+    SharedPlainLetter(Hint, Hint),
+}
+
+/// Lowercases `text`, drops everything except letters and whitespace and
+/// separates words with single spaces, which is the form vocabulary words
+/// and cipher words are compared in.
+pub fn normalize(text: &str) -> String {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .flat_map(char::to_lowercase)
+                .filter(char::is_ascii_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.join(" ")
 }
 
 impl CipherText {
     pub fn new(text: String) -> CipherText {
+        CipherText::with_hints(text, Vec::new())
+    }
+
+    /// Like `with_hints`, but returns an error for texts that don't fit the
+    /// search instead of panicking, and for hints which can't all hold: a hint
+    /// for a letter missing from the text, two plain letters for one cipher
+    /// letter or one plain letter for two cipher letters.
+    pub fn try_with_hints(text: String, hints: Vec<Hint>) -> Result<CipherText, CipherTextError> {
+        let normalized = normalize(&text);
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+        if words.len() > MAX_WORDS {
+            return Err(CipherTextError::TooManyWords(words.len()));
+        }
+        if let Some(word) = words.iter().find(|word| word.len() > MAX_WORD_LENGTH) {
+            return Err(CipherTextError::WordTooLong(word.to_string()));
+        }
+        for (index, &hint) in hints.iter().enumerate() {
+            if !normalized.contains(hint.cipher) {
+                return Err(CipherTextError::HintNotInText(hint));
+            }
+            for &earlier in &hints[..index] {
+                if earlier.cipher == hint.cipher && earlier.plain != hint.plain {
+                    return Err(CipherTextError::ConflictingHints(earlier, hint));
+                }
+                if earlier.plain == hint.plain && earlier.cipher != hint.cipher {
+                    return Err(CipherTextError::SharedPlainLetter(earlier, hint));
+                }
+            }
+        }
+        Ok(CipherText::with_hints(text, hints))
+    }
+
+    /// Every hinted letter becomes a condition even if it occurs once, hinted
+    /// conditions allow a single letter so they go first.
+    pub fn with_hints(text: String, hints: Vec<Hint>) -> CipherText {
+        let text = normalize(&text);
         let mut char_map: HashMap<char, Vec<CipherChar>> = HashMap::new();
         let mut lengths = Vec::new();
-        let word_count = text.split_whitespace().enumerate().count();
         for (cipher_word_id, cipher_word) in text.split_whitespace().enumerate() {
-            assert!(cipher_word_id < 256);
-            assert!(cipher_word.len() < 256);
+            assert!(cipher_word_id < MAX_WORDS);
+            assert!(cipher_word.len() <= MAX_WORD_LENGTH);
             let length = cipher_word.len() as u8;
             lengths.push(length as usize);
             for (char_position, ch) in cipher_word.char_indices() {
                 char_map
                     .entry(ch)
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(CipherChar {
                         position: Position(char_position as u8),
                         cipher_word_id: CipherWordId(cipher_word_id as u8),
@@ -49,31 +132,90 @@ impl CipherText {
         }
         let mut conditions: Vec<Condition> = char_map
             .into_iter()
-            .filter(|&(_, ref equal_chars)| equal_chars.len() > 1)
-            .map(|(_, equal_chars)| Condition { equal_chars })
+            .map(|(cipher_char, equal_chars)| Condition {
+                cipher_char,
+                equal_chars,
+                hint: hints
+                    .iter()
+                    .find(|hint| hint.cipher == cipher_char)
+                    .map(|hint| hint.plain),
+                no_self_mapping: false,
+            })
+            .filter(|condition| condition.equal_chars.len() > 1 || condition.hint.is_some())
             .collect();
 
-        conditions.sort_unstable_by_key(|condition| condition.score());
+        conditions.sort_unstable_by_key(|condition| {
+            (condition.hint.is_some(), condition.score(), Reverse(condition.first_occurrence()))
+        });
 
         conditions.reverse();
 
         CipherText {
             text,
             conditions,
-            word_count,
             lengths,
+            hints,
+            no_self_mapping: false,
         }
     }
 
+    /// Forbids letters to encrypt to themselves, as in Aristocrat puzzles.
+    /// Hinted letters are still taken as given.
+    pub fn without_self_mapping(mut self) -> CipherText {
+        self.no_self_mapping = true;
+        for condition in &mut self.conditions {
+            condition.no_self_mapping = true;
+        }
+        self
+    }
+
+    pub fn forbids_self_mapping(&self) -> bool {
+        self.no_self_mapping
+    }
+
+    /// True when `plain_word` reads a letter of `cipher_word` as itself and
+    /// the letter isn't hinted.
+    pub fn maps_to_itself(&self, cipher_word: &str, plain_word: &str) -> bool {
+        cipher_word
+            .chars()
+            .zip(plain_word.chars())
+            .any(|(cipher, plain)| cipher == plain && !self.hints.iter().any(|hint| hint.cipher == cipher))
+    }
+
+    /// Cipher words without letters of any condition, which may be any
+    /// vocabulary word of their length.
+    pub fn unconstrained_words(&self) -> Vec<CipherWordId> {
+        let constrained: HashSet<CipherWordId> = self.conditions
+            .iter()
+            .flat_map(|condition| condition.equal_chars.iter().map(|ch| ch.cipher_word_id))
+            .collect();
+        (0..self.lengths.len())
+            .map(CipherWordId::from)
+            .filter(|id| !constrained.contains(id))
+            .collect()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn words(&self) -> Vec<&str> {
+        self.text.split_whitespace().collect()
+    }
+
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
     pub fn reorder_conditions(&mut self, reorder: &[usize]) {
         assert_eq!(reorder.len(), self.conditions.len());
         let conditions_len = self.conditions.len();
         let reordered_conditions = reorder.iter()
-            .map(|index| *index)
+            .copied()
             .inspect(|index| assert!(*index <= conditions_len))
             .map(|index| self.conditions[index - 1].clone())
             .collect();
-        ::std::mem::replace(&mut self.conditions, reordered_conditions);
+        self.conditions = reordered_conditions;
     }
 
     pub fn conditions(&self) -> &[Condition] {
@@ -85,12 +227,49 @@ impl CipherText {
     }
 }
 
+impl CipherWordId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<usize> for CipherWordId {
+    fn from(index: usize) -> CipherWordId {
+        assert!(index < 256);
+        CipherWordId(index as u8)
+    }
+}
+
 impl Condition {
     pub fn equal_chars(&self) -> &[CipherChar] {
         &self.equal_chars
     }
 
-    fn score(&self) -> usize {
+    pub fn cipher_char(&self) -> char {
+        self.cipher_char
+    }
+
+    pub fn hint(&self) -> Option<Char> {
+        self.hint
+    }
+
+    /// Plain letters the search tries for this condition.
+    pub fn letters(&self) -> AlphabetIter {
+        match self.hint {
+            Some(plain) => AlphabetIter::only(plain),
+            None if self.no_self_mapping => AlphabetIter::new().except(Char::from(self.cipher_char)),
+            None => AlphabetIter::new(),
+        }
+    }
+
+    fn first_occurrence(&self) -> (CipherWordId, u8) {
+        let first = &self.equal_chars[0];
+        (first.cipher_word_id, first.position.0)
+    }
+
+    /// Ten points for every cipher word the letter is in and one for every
+    /// occurrence, conditions with higher scores are searched first.
+    pub fn score(&self) -> usize {
         let different_words = self.equal_chars
             .iter()
             .fold(HashSet::new(), |mut set, cipher_char| {
@@ -118,9 +297,9 @@ impl fmt::Debug for Condition {
 
 impl fmt::Display for CipherText {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "initial text: \"{}\"\n", &self.text)?;
+        writeln!(f, "initial text: \"{}\"", &self.text)?;
         for (index, condition) in self.conditions.iter().enumerate() {
-            write!(f, "    {}) {}\n", index + 1, &condition)?;
+            writeln!(f, "    {}) {}", index + 1, &condition)?;
         }
         Ok(())
     }
@@ -135,6 +314,110 @@ impl fmt::Display for Condition {
                 write!(f, "{}[{:?}]", ch.cipher_word_id.0, ch.position)?;
             }
         }
+        if let Some(hint) = self.hint {
+            write!(f, " == {:?}", hint)?;
+        }
         Ok(())
     }
 }
+
+impl FromStr for Hint {
+    type Err = String;
+
+    fn from_str(hint: &str) -> Result<Hint, String> {
+        let hint = hint.to_lowercase();
+        let mut chars = hint.chars();
+        match (chars.next(), chars.next(), chars.next(), chars.next()) {
+            (Some(cipher), Some('='), Some(plain), None)
+                if cipher.is_ascii_lowercase() && plain.is_ascii_lowercase() =>
+            {
+                Ok(Hint {
+                    cipher,
+                    plain: Char::from(plain),
+                })
+            }
+            _ => Err(format!("invalid hint {}, expected cipher and plain letter like q=e", hint)),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}={:?}", self.cipher, self.plain)
+    }
+}
+
+impl fmt::Display for CipherTextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            CipherTextError::TooManyWords(words) => {
+                write!(f, "cipher text has {} words, at most {} are allowed", words, MAX_WORDS)
+            }
+            CipherTextError::WordTooLong(ref word) => write!(
+                f,
+                "cipher word has {} letters, at most {} are allowed",
+                word.len(),
+                MAX_WORD_LENGTH
+            ),
+            CipherTextError::HintNotInText(hint) => {
+                write!(f, "hint {} is for letter {} which is not in the cipher text", hint, hint.cipher)
+            }
+            CipherTextError::ConflictingHints(first, second) => {
+                write!(f, "hints {} and {} give two plain letters for {}", first, second, first.cipher)
+            }
+            CipherTextError::SharedPlainLetter(first, second) => write!(
+                f,
+                "hints {} and {} give plain letter {:?} to two cipher letters",
+                first, second, first.plain
+            ),
+        }
+    }
+}
+
+impl Error for CipherTextError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters(condition: &Condition) -> Vec<char> {
+        condition.letters().map(|ch| ch.0 as char).collect()
+    }
+
+    #[test]
+    fn conditions_skip_their_own_letter_without_self_mapping() {
+        let cipher_text = CipherText::new("abc cba".to_string());
+        assert!(cipher_text.conditions().iter().all(|condition| condition.letters().count() == 26));
+
+        let cipher_text = cipher_text.without_self_mapping();
+        assert!(cipher_text.forbids_self_mapping());
+        for condition in cipher_text.conditions() {
+            let letters = letters(condition);
+            assert_eq!(letters.len(), 25);
+            assert!(!letters.contains(&condition.cipher_char()));
+        }
+    }
+
+    #[test]
+    fn hinted_letters_may_map_to_themselves() {
+        let cipher_text = CipherText::try_with_hints("abc cba".to_string(), vec!["a=a".parse().unwrap()])
+            .unwrap()
+            .without_self_mapping();
+        let hinted = cipher_text
+            .conditions()
+            .iter()
+            .find(|condition| condition.cipher_char() == 'a')
+            .unwrap();
+        assert_eq!(letters(hinted), vec!['a']);
+        assert!(!cipher_text.maps_to_itself("abc", "axx"));
+        assert!(cipher_text.maps_to_itself("abc", "xbx"));
+        assert!(!cipher_text.maps_to_itself("abc", "xyz"));
+    }
+
+    #[test]
+    fn lists_words_outside_of_conditions() {
+        let cipher_text = CipherText::new("abc cba xyz".to_string());
+        assert_eq!(cipher_text.unconstrained_words(), vec![CipherWordId::from(2)]);
+        assert!(CipherText::new("abc cba".to_string()).unconstrained_words().is_empty());
+    }
+}
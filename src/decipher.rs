@@ -1,86 +1,426 @@
+use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::iter::Iterator;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use rayon;
+use rayon::prelude::*;
 
 use vocabulary_index::{VocabularyIndex, Words};
 use cipher_text::{CipherChar, CipherText, CipherWordId, Condition};
-use vocabulary::{AlphabetIter, Char};
+use vocabulary::{AlphabetIter, Char, Vocabulary};
+
+const PROGRESS_INTERVAL: u64 = 1000;
+const PARALLEL_SPLIT_DEPTH: usize = 2;
+/// A search stops once it has found this many partial solutions.
+pub const MAX_SOLUTIONS: usize = 10_000;
 
 pub struct Decipher<'r> {
-    index: VocabularyIndex,
+    index: Arc<VocabularyIndex>,
     cipher_text: &'r CipherText,
 }
 
+#[derive(Clone)]
 pub struct Solution {
     solution: Vec<PartialSolution>,
+    statistics: Statistics,
+    complete: bool,
+}
+
+/// How the search went, condition by condition.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    pub nodes_explored: u64,
+    pub conditions: Vec<ConditionStatistics>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConditionStatistics {
+    /// How many times the search reached this condition.
+    pub visits: u64,
+    /// Letters tried for this condition.
+    pub tried: u64,
+    /// Letters that were consistent with the conditions above.
+    pub accepted: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub nodes_explored: u64,
+    pub depth: usize,
+    pub max_depth: usize,
+    pub fraction_complete: f64,
+    pub solutions_found: usize,
+}
+
+/// Tells the search whether to go on after a solution is found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Stop,
 }
 
+/// One step of the search, see `Decipher::find_solution_traced`.
 #[derive(Debug)]
+pub enum Step<'a> {
+    /// `letter` was tried for the condition at `depth`. `candidates` are the
+    /// candidate words after intersecting with the conditions above, `None`
+    /// when some cipher word has no candidates left.
+    Tried {
+        depth: usize,
+        condition: &'a Condition,
+        letter: Char,
+        candidates: Option<&'a PartialSolution>,
+    },
+    /// Every letter of the condition at `depth` was tried, the search goes back
+    /// to the condition above.
+    Backtracked { depth: usize },
+}
+
+#[derive(Debug, Clone)]
 pub struct PartialSolution {
     satisfactory_words: HashMap<CipherWordId, Words>,
 }
 
 struct BacktrackingSearch<'r> {
+    cipher_text: &'r CipherText,
     rules: &'r [Condition],
     solutions: Vec<(AlphabetIter, PartialSolution)>,
     current: AlphabetIter,
     full_solutions: Vec<PartialSolution>,
-    index: &'r VocabularyIndex
+    index: &'r VocabularyIndex,
+    nodes_explored: u64,
+    statistics: Vec<ConditionStatistics>,
+    complete: bool,
 }
 
 impl<'r> Decipher<'r> {
-    pub fn new(index: VocabularyIndex, cipher_text: &'r CipherText) -> Decipher<'r> {
+    pub fn new(index: Arc<VocabularyIndex>, cipher_text: &'r CipherText) -> Decipher<'r> {
         Decipher { index, cipher_text }
     }
 
     pub fn find_solution(&self) -> Solution {
-        let mut search = BacktrackingSearch {
-            rules:   self.cipher_text.conditions(),
-            solutions: Vec::new(),
-            current: AlphabetIter::new(),
+        self.find_solution_with_progress(|_| ())
+    }
+
+    /// Same as `find_solution`, but calls `on_progress` every `PROGRESS_INTERVAL`
+    /// explored nodes and once more when the search is over.
+    pub fn find_solution_with_progress<F: FnMut(&Progress)>(&self, mut on_progress: F) -> Solution {
+        self.find_solution_with(
+            |progress| {
+                on_progress(progress);
+                Flow::Continue
+            },
+            |_| Flow::Continue,
+        )
+    }
+
+    /// Same as `find_solution_with_progress`, but also passes every found partial
+    /// solution to `on_solution`. Either callback may stop the search early,
+    /// which leaves the solution incomplete.
+    pub fn find_solution_with<F, S>(&self, mut on_progress: F, mut on_solution: S) -> Solution
+    where
+        F: FnMut(&Progress) -> Flow,
+        S: FnMut(&PartialSolution) -> Flow,
+    {
+        let mut search = self.search(Vec::new());
+        search.run(0, &mut on_progress, &mut on_solution, &mut |_| ());
+
+        let mut progress = search.progress(0);
+        progress.fraction_complete = 1.;
+        on_progress(&progress);
+
+        Solution {
+            statistics: search.statistics(),
+            complete: search.complete,
+            solution: search.full_solutions,
+        }
+    }
+
+    /// Same as `find_solution`, but passes every step of the search to `on_step`.
+    pub fn find_solution_traced<T: FnMut(&Step)>(&self, mut on_step: T) -> Solution {
+        let mut search = self.search(Vec::new());
+        search.run(0, &mut |_| Flow::Continue, &mut |_| Flow::Continue, &mut on_step);
+        Solution {
+            statistics: search.statistics(),
+            complete: search.complete,
+            solution: search.full_solutions,
+        }
+    }
+
+    pub fn find_solution_parallel(&self, threads: usize) -> Solution {
+        self.find_solution_parallel_with_progress(threads, |_| ())
+    }
+
+    /// Splits the search tree on the letters of the first `PARALLEL_SPLIT_DEPTH`
+    /// conditions and explores the subtrees on a work-stealing pool of `threads`
+    /// threads (all cores when `threads` is 0). Solutions come out in the same
+    /// order as from `find_solution`. When the search stops at `MAX_SOLUTIONS`
+    /// the solutions kept depend on thread scheduling and differ between runs.
+    pub fn find_solution_parallel_with_progress<F: FnMut(&Progress) + Send>(
+        &self,
+        threads: usize,
+        on_progress: F,
+    ) -> Solution {
+        let split_depth = cmp::min(PARALLEL_SPLIT_DEPTH, self.cipher_text.conditions().len().saturating_sub(1));
+        if split_depth == 0 {
+            return self.find_solution_with_progress(on_progress);
+        }
+
+        let mut statistics = Statistics::new(self.cipher_text.conditions().len());
+        let prefixes = self.prefixes(split_depth, &mut statistics);
+        let total_tasks = prefixes.len();
+        let nodes_explored = AtomicU64::new(0);
+        let solutions_found = AtomicUsize::new(0);
+        let tasks_done = AtomicUsize::new(0);
+        let on_progress = Mutex::new(on_progress);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to build thread pool");
+
+        let solutions: Vec<(Vec<PartialSolution>, Statistics, bool)> = pool.install(|| {
+            prefixes
+                .into_par_iter()
+                .map(|prefix| {
+                    let mut search = self.search(prefix);
+                    if solutions_found.load(Ordering::Relaxed) >= MAX_SOLUTIONS {
+                        tasks_done.fetch_add(1, Ordering::Relaxed);
+                        return (Vec::new(), search.statistics(), false);
+                    }
+                    let mut on_local_progress = |local: &Progress| {
+                        let done = tasks_done.load(Ordering::Relaxed) as f64;
+                        let progress = Progress {
+                            nodes_explored: nodes_explored.fetch_add(PROGRESS_INTERVAL, Ordering::Relaxed)
+                                + PROGRESS_INTERVAL,
+                            depth: local.depth,
+                            max_depth: local.max_depth,
+                            fraction_complete: (done + local.fraction_complete) / total_tasks as f64,
+                            solutions_found: solutions_found.load(Ordering::Relaxed),
+                        };
+                        (on_progress.lock().unwrap())(&progress);
+                        if progress.solutions_found < MAX_SOLUTIONS {
+                            Flow::Continue
+                        } else {
+                            Flow::Stop
+                        }
+                    };
+                    // The cap is shared by all tasks, so the search stops at
+                    // MAX_SOLUTIONS solutions in total like a sequential one.
+                    let mut on_solution = |_: &PartialSolution| {
+                        if solutions_found.fetch_add(1, Ordering::Relaxed) + 1 < MAX_SOLUTIONS {
+                            Flow::Continue
+                        } else {
+                            Flow::Stop
+                        }
+                    };
+                    search.run(split_depth, &mut on_local_progress, &mut on_solution, &mut |_| ());
+                    nodes_explored.fetch_add(search.nodes_explored % PROGRESS_INTERVAL, Ordering::Relaxed);
+                    tasks_done.fetch_add(1, Ordering::Relaxed);
+                    {
+                        let statistics = search.statistics();
+                        (search.full_solutions, statistics, search.complete)
+                    }
+                })
+                .collect()
+        });
+
+        let mut solution = Vec::new();
+        let mut complete = true;
+        for (task_solutions, task_statistics, task_complete) in solutions {
+            solution.extend(task_solutions);
+            statistics.add(&task_statistics);
+            complete &= task_complete;
+        }
+        // Tasks reaching the cap at the same time may overshoot it.
+        solution.truncate(MAX_SOLUTIONS);
+
+        let mut on_progress = on_progress.into_inner().unwrap();
+        on_progress(&Progress {
+            nodes_explored: nodes_explored.load(Ordering::Relaxed),
+            depth: 0,
+            max_depth: self.cipher_text.conditions().len(),
+            fraction_complete: 1.,
+            solutions_found: solution.len(),
+        });
+
+        Solution {
+            solution,
+            statistics,
+            complete,
+        }
+    }
+
+    fn search(&self, solutions: Vec<(AlphabetIter, PartialSolution)>) -> BacktrackingSearch<'_> {
+        let rules = self.cipher_text.conditions();
+        BacktrackingSearch {
+            cipher_text: self.cipher_text,
+            rules,
+            current: letters_of(rules.get(solutions.len())),
+            solutions,
             full_solutions: Vec::new(),
-            index: &self.index
-        };
+            index: &self.index,
+            nodes_explored: 0,
+            statistics: vec![ConditionStatistics::default(); rules.len()],
+            complete: true,
+        }
+    }
+
+    /// Lists every consistent assignment of letters to the first `depth` conditions
+    /// in the order the sequential search would visit them.
+    /// The letters tried on the way are recorded in `statistics`.
+    fn prefixes(&self, depth: usize, statistics: &mut Statistics) -> Vec<Vec<(AlphabetIter, PartialSolution)>> {
+        let search = self.search(Vec::new());
+        let mut prefixes: Vec<Vec<(AlphabetIter, PartialSolution)>> = vec![Vec::new()];
+        for (rule, rule_statistics) in search.rules[..depth].iter().zip(statistics.conditions.iter_mut()) {
+            let mut next_prefixes = Vec::new();
+            for prefix in prefixes {
+                rule_statistics.visits += 1;
+                let mut char_iter = rule.letters();
+                while let Some(ch) = char_iter.next() {
+                    statistics.nodes_explored += 1;
+                    rule_statistics.tried += 1;
+                    let found = match search.partial_solution(rule, ch) {
+                        Some(found) => found,
+                        None => continue,
+                    };
+                    let solution = match prefix.last() {
+                        Some((_, last)) => match last.intersect(&found) {
+                            Some(solution) => solution,
+                            None => continue,
+                        },
+                        None => found,
+                    };
+                    rule_statistics.accepted += 1;
+                    let mut next_prefix = prefix.clone();
+                    next_prefix.push((char_iter.clone(), solution));
+                    next_prefixes.push(next_prefix);
+                }
+            }
+            prefixes = next_prefixes;
+        }
+        prefixes
+    }
+}
 
+impl<'r> BacktrackingSearch<'r> {
+    /// Explores the subtree below the first `floor` entries of `solutions`,
+    /// which are never backtracked.
+    fn run<F, S, T>(&mut self, floor: usize, on_progress: &mut F, on_solution: &mut S, on_step: &mut T)
+    where
+        F: FnMut(&Progress) -> Flow,
+        S: FnMut(&PartialSolution) -> Flow,
+        T: FnMut(&Step),
+    {
+        if self.rules.is_empty() || !self.unconstrained_words_readable() {
+            return;
+        }
+        self.statistics[floor].visits += 1;
         loop {
-            if let Some(next_char) = search.current.next() {
-                if search.solutions.len() == 0 {
-                    debug!("{}% complete", ((next_char.0 - 'a' as u8) as f32 / 26.) * 100.);
+            if let Some(next_char) = self.current.next() {
+                self.nodes_explored += 1;
+                self.statistics[self.solutions.len()].tried += 1;
+                if self.nodes_explored.is_multiple_of(PROGRESS_INTERVAL)
+                    && on_progress(&self.progress(floor)) == Flow::Stop
+                {
+                    self.complete = false;
+                    break;
                 }
-                if let Some(solution) = search.partial_solution_intersected_with_top_solution(next_char) {
-                    if search.solutions.len() == search.rules.len() - 1 {
-                        search.full_solutions.push(solution);
-                        let solution_count = search.full_solutions.len();
-                        if solution_count % 10 == 0 {
-                            debug!("found solution №{}", search.full_solutions.len());
-                        }
-                        if search.full_solutions.len() > 10_000 {
-                            println!("Too many solutions!");
-                            ::std::process::exit(1);
+                let found = self.partial_solution_intersected_with_top_solution(next_char);
+                on_step(&Step::Tried {
+                    depth: self.solutions.len(),
+                    condition: self.current_rule(),
+                    letter: next_char,
+                    candidates: found.as_ref(),
+                });
+                if let Some(solution) = found {
+                    self.statistics[self.solutions.len()].accepted += 1;
+                    if self.solutions.len() == self.rules.len() - 1 {
+                        let flow = on_solution(&solution);
+                        self.full_solutions.push(solution);
+                        if flow == Flow::Stop || self.full_solutions.len() >= MAX_SOLUTIONS {
+                            self.complete = false;
+                            break;
                         }
                     } else {
-                        let current_char_iter = ::std::mem::replace(&mut search.current, AlphabetIter::new());
-                        search.solutions.push((current_char_iter, solution));
+                        let next_letters = self.rules[self.solutions.len() + 1].letters();
+                        let current_char_iter = ::std::mem::replace(&mut self.current, next_letters);
+                        self.solutions.push((current_char_iter, solution));
+                        self.statistics[self.solutions.len()].visits += 1;
                     }
                 }
-            } else if search.solutions.is_empty() {
+            } else if self.solutions.len() == floor {
                 break;
-            } else if let Some((last_char_iter, _)) = search.solutions.pop() {
-                search.current = last_char_iter;
+            } else if let Some((last_char_iter, _)) = self.solutions.pop() {
+                on_step(&Step::Backtracked {
+                    depth: self.solutions.len() + 1,
+                });
+                self.current = last_char_iter;
             }
         }
+    }
 
-        Solution {
-            solution: search.full_solutions
+    /// False when self-mapping is forbidden and some cipher word outside of
+    /// every condition has no vocabulary word of its length avoiding it, so
+    /// no partial solution can be read without self-mapping.
+    fn unconstrained_words_readable(&self) -> bool {
+        if !self.cipher_text.forbids_self_mapping() {
+            return true;
         }
+        let vocabulary = self.index.vocabulary();
+        let cipher_words = self.cipher_text.words();
+        self.cipher_text.unconstrained_words().into_iter().all(|cipher_word_id| {
+            let cipher_word = cipher_words[cipher_word_id.index()];
+            vocabulary.by_length().get(cipher_word.len()).is_some_and(|words| {
+                words
+                    .iter()
+                    .any(|word_id| !self.cipher_text.maps_to_itself(cipher_word, vocabulary.get(*word_id).unwrap()))
+            })
+        })
     }
-}
 
-impl<'r> BacktrackingSearch<'r> {
+    fn statistics(&self) -> Statistics {
+        Statistics {
+            nodes_explored: self.nodes_explored,
+            conditions: self.statistics.clone(),
+        }
+    }
+
+    fn progress(&self, floor: usize) -> Progress {
+        Progress {
+            nodes_explored: self.nodes_explored,
+            depth: self.solutions.len(),
+            max_depth: self.rules.len(),
+            fraction_complete: self.fraction_complete(floor),
+            solutions_found: self.full_solutions.len(),
+        }
+    }
+
+    /// Estimates the explored part of the subtree below `floor` assuming that
+    /// every letter of every condition spans a subtree of the same size.
+    fn fraction_complete(&self, floor: usize) -> f64 {
+        let mut fraction = 0.;
+        let mut subtree_size = 1.;
+        for (char_iter, _) in &self.solutions[floor..] {
+            subtree_size /= f64::from(char_iter.size());
+            fraction += f64::from(char_iter.yielded() - 1) * subtree_size;
+        }
+        subtree_size /= f64::from(self.current.size());
+        fraction + f64::from(self.current.yielded()) * subtree_size
+    }
+
+    /// At the last condition the candidates which read some single-occurrence
+    /// letter as itself are dropped too when self-mapping is forbidden.
     fn partial_solution_intersected_with_top_solution(&self, ch: Char) -> Option<PartialSolution> {
-        let found = self.partial_solution(self.current_rule(), ch)?;
-        if let Some(&(_, ref last)) = self.solutions.last() {
-            return last.intersect(&found);
+        let mut found = self.partial_solution(self.current_rule(), ch)?;
+        if let Some((_, last)) = self.solutions.last() {
+            found = last.intersect(&found)?;
+        }
+        if self.cipher_text.forbids_self_mapping() && self.solutions.len() == self.rules.len() - 1 {
+            return found.without_self_mapping(self.cipher_text, self.index.vocabulary());
         }
         Some(found)
     }
@@ -117,17 +457,136 @@ impl<'r> BacktrackingSearch<'r> {
     }
 }
 
+fn letters_of(rule: Option<&Condition>) -> AlphabetIter {
+    rule.map(Condition::letters).unwrap_or_default()
+}
+
 impl Solution {
     pub fn partial_solutions(&self) -> &[PartialSolution] {
         &self.solution
     }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// False when the search was stopped before exploring the whole tree,
+    /// by a callback or after `MAX_SOLUTIONS` solutions.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Orders partial solutions by `PartialSolution::score`, best first.
+    /// Equally scored ones keep the order they were found in.
+    pub fn rank(&mut self, vocabulary: &Vocabulary) {
+        let mut scored: Vec<(f64, PartialSolution)> = self.solution
+            .drain(..)
+            .map(|partial_solution| (partial_solution.score(vocabulary), partial_solution))
+            .collect();
+        scored.sort_by(|left, right| right.0.partial_cmp(&left.0).unwrap_or(cmp::Ordering::Equal));
+        self.solution = scored.into_iter().map(|(_, partial_solution)| partial_solution).collect();
+    }
+}
+
+impl Statistics {
+    fn new(conditions: usize) -> Statistics {
+        Statistics {
+            nodes_explored: 0,
+            conditions: vec![ConditionStatistics::default(); conditions],
+        }
+    }
+
+    fn add(&mut self, other: &Statistics) {
+        self.nodes_explored += other.nodes_explored;
+        for (condition, other) in self.conditions.iter_mut().zip(&other.conditions) {
+            condition.visits += other.visits;
+            condition.tried += other.tried;
+            condition.accepted += other.accepted;
+        }
+    }
+}
+
+impl ConditionStatistics {
+    /// Average number of consistent letters per visit of the condition.
+    pub fn branching_factor(&self) -> f64 {
+        if self.visits == 0 {
+            0.
+        } else {
+            self.accepted as f64 / self.visits as f64
+        }
+    }
 }
 
 impl PartialSolution {
+    /// Partial solution of a cipher text without conditions, all words are free.
+    pub fn unconstrained() -> PartialSolution {
+        PartialSolution {
+            satisfactory_words: HashMap::new(),
+        }
+    }
+
     pub fn satisfactory_words(&self) -> &HashMap<CipherWordId, Words> {
         &self.satisfactory_words
     }
 
+    /// Plain letters of cipher letters which every candidate reads the same
+    /// way, hinted letters included, sorted by cipher letter.
+    pub fn key(&self, cipher_text: &CipherText, vocabulary: &Vocabulary) -> Vec<(char, Char)> {
+        let mut plain_by_cipher: HashMap<char, Option<u8>> = HashMap::new();
+        for (index, cipher_word) in cipher_text.words().into_iter().enumerate() {
+            let words = match self.satisfactory_words.get(&CipherWordId::from(index)) {
+                Some(words) => words,
+                None => continue,
+            };
+            for (position, cipher_char) in cipher_word.chars().enumerate() {
+                for word_id in words.ids() {
+                    let plain = vocabulary.get(*word_id).unwrap().as_bytes()[position];
+                    let known = plain_by_cipher.entry(cipher_char).or_insert(Some(plain));
+                    if *known != Some(plain) {
+                        *known = None;
+                    }
+                }
+            }
+        }
+        for hint in cipher_text.hints() {
+            plain_by_cipher.insert(hint.cipher, Some(hint.plain.0));
+        }
+        let mut key: Vec<(char, Char)> = plain_by_cipher
+            .into_iter()
+            .filter_map(|(cipher_char, plain)| plain.map(|plain| (cipher_char, Char(plain))))
+            .collect();
+        key.sort_by_key(|&(cipher_char, _)| cipher_char);
+        key
+    }
+
+    /// Sum of the highest word weight among candidates of every cipher word.
+    pub fn score(&self, vocabulary: &Vocabulary) -> f64 {
+        self.satisfactory_words
+            .values()
+            .map(|words| {
+                words
+                    .ids()
+                    .iter()
+                    .map(|word_id| vocabulary.weight(*word_id))
+                    .fold(0., f64::max)
+            })
+            .sum()
+    }
+
+    /// Drops candidates with a plain letter equal to its cipher letter,
+    /// None when some cipher word has no candidates left.
+    fn without_self_mapping(mut self, cipher_text: &CipherText, vocabulary: &Vocabulary) -> Option<PartialSolution> {
+        let cipher_words = cipher_text.words();
+        for (cipher_word_id, words) in &mut self.satisfactory_words {
+            let cipher_word = cipher_words[cipher_word_id.index()];
+            words.retain(|word_id| !cipher_text.maps_to_itself(cipher_word, vocabulary.get(*word_id).unwrap()));
+            if words.is_empty() {
+                return None;
+            }
+        }
+        Some(self)
+    }
+
     fn intersect(&self, other: &PartialSolution) -> Option<PartialSolution> {
         let left_words: HashSet<CipherWordId> = self.satisfactory_words.keys().cloned().collect();
         let right_words: HashSet<CipherWordId> = other.satisfactory_words.keys().cloned().collect();
@@ -169,3 +628,186 @@ impl fmt::Debug for Solution {
         write!(f, "Solution {{ total_entries: {} }}", self.solution.len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vocabulary::WordId;
+
+    /// Every three letter word of the letters a to f, a dense vocabulary
+    /// giving many solutions.
+    fn index() -> Arc<VocabularyIndex> {
+        let letters = "abcdef";
+        let mut words = Vec::new();
+        for first in letters.chars() {
+            for second in letters.chars() {
+                for third in letters.chars() {
+                    words.push(format!("{}{}{}", first, second, third));
+                }
+            }
+        }
+        Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new(words.join("\n")))))
+    }
+
+    /// Candidate word ids of every cipher word of every partial solution.
+    fn candidates(solution: &Solution) -> Vec<Vec<(usize, Vec<usize>)>> {
+        solution
+            .partial_solutions()
+            .iter()
+            .map(|partial_solution| {
+                let mut words: Vec<(usize, Vec<usize>)> = partial_solution
+                    .satisfactory_words()
+                    .iter()
+                    .map(|(cipher_word_id, words)| {
+                        (cipher_word_id.index(), words.ids().iter().map(|&WordId(id)| id).collect())
+                    })
+                    .collect();
+                words.sort();
+                words
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_search_finds_the_sequential_solutions_in_order() {
+        let index = index();
+        let cipher_text = CipherText::new("abc bcd cde".to_string());
+        let decipher = Decipher::new(index, &cipher_text);
+        let sequential = decipher.find_solution();
+        assert!(sequential.is_complete());
+        assert!(sequential.partial_solutions().len() > 1);
+        for threads in 1..4 {
+            let parallel = decipher.find_solution_parallel(threads);
+            assert!(parallel.is_complete());
+            assert_eq!(candidates(&parallel), candidates(&sequential));
+            assert_eq!(parallel.statistics().nodes_explored, sequential.statistics().nodes_explored);
+        }
+    }
+
+    #[test]
+    fn reports_growing_progress() {
+        let cipher_text = CipherText::new("abc bcd cde def".to_string());
+        let mut reports: Vec<Progress> = Vec::new();
+        let solution = Decipher::new(index(), &cipher_text).find_solution_with_progress(|progress| {
+            reports.push(progress.clone())
+        });
+        let nodes_explored = solution.statistics().nodes_explored;
+        assert_eq!(reports.len() as u64, nodes_explored / PROGRESS_INTERVAL + 1);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].fraction_complete <= pair[1].fraction_complete
+                && pair[0].nodes_explored < pair[1].nodes_explored
+                && pair[0].solutions_found <= pair[1].solutions_found));
+        assert!(reports
+            .iter()
+            .all(|progress| progress.fraction_complete >= 0. && progress.fraction_complete <= 1.
+                && progress.max_depth == 4 && progress.depth < 4));
+        let last = reports.last().unwrap();
+        assert_eq!(last.fraction_complete, 1.);
+        assert_eq!(last.nodes_explored, nodes_explored);
+        assert_eq!(last.solutions_found, solution.partial_solutions().len());
+    }
+
+    #[test]
+    fn reports_progress_of_parallel_search() {
+        let cipher_text = CipherText::new("abc bcd cde def".to_string());
+        let mut last: Option<Progress> = None;
+        let solution = Decipher::new(index(), &cipher_text)
+            .find_solution_parallel_with_progress(2, |progress| last = Some(progress.clone()));
+        let last = last.unwrap();
+        assert_eq!(last.fraction_complete, 1.);
+        assert_eq!(last.solutions_found, solution.partial_solutions().len());
+    }
+
+    #[test]
+    fn stops_when_progress_callback_asks() {
+        let cipher_text = CipherText::new("abc bcd cde def".to_string());
+        let complete = Decipher::new(index(), &cipher_text).find_solution();
+        let mut reports = 0;
+        let stopped = Decipher::new(index(), &cipher_text).find_solution_with(
+            |_| {
+                reports += 1;
+                if reports < 3 {
+                    Flow::Continue
+                } else {
+                    Flow::Stop
+                }
+            },
+            |_| Flow::Continue,
+        );
+        assert!(!stopped.is_complete());
+        assert_eq!(stopped.statistics().nodes_explored, 3 * PROGRESS_INTERVAL);
+        assert!(stopped.partial_solutions().len() < complete.partial_solutions().len());
+    }
+
+    #[test]
+    fn stops_when_solution_callback_asks() {
+        let cipher_text = CipherText::new("abc bcd cde def".to_string());
+        let mut found = 0;
+        let stopped = Decipher::new(index(), &cipher_text).find_solution_with(
+            |_| Flow::Continue,
+            |_| {
+                found += 1;
+                if found < 5 {
+                    Flow::Continue
+                } else {
+                    Flow::Stop
+                }
+            },
+        );
+        assert!(!stopped.is_complete());
+        assert_eq!(stopped.partial_solutions().len(), 5);
+    }
+
+    #[test]
+    fn forbids_self_mapping() {
+        let index = index();
+        let cipher_text = CipherText::new("abc bcd cde def".to_string());
+        let all = Decipher::new(index.clone(), &cipher_text).find_solution();
+        let cipher_text = cipher_text.without_self_mapping();
+        let solution = Decipher::new(index.clone(), &cipher_text).find_solution();
+        assert!(solution.is_complete());
+        assert!(!solution.partial_solutions().is_empty());
+        assert!(solution.partial_solutions().len() < all.partial_solutions().len());
+
+        let cipher_words = cipher_text.words();
+        for partial_solution in solution.partial_solutions() {
+            for (cipher_word_id, words) in partial_solution.satisfactory_words() {
+                let cipher_word = cipher_words[cipher_word_id.index()];
+                for word_id in words.ids() {
+                    let word = index.vocabulary().get(*word_id).unwrap();
+                    assert!(!cipher_text.maps_to_itself(cipher_word, word), "{} read as {}", cipher_word, word);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn drops_words_reading_single_letters_as_themselves() {
+        // `a` occurs once, so no condition keeps `tan` from reading it as itself.
+        let index = Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new("tan\ntin\nnt".to_string()))));
+        let cipher_text = CipherText::new("xaz zx".to_string());
+        let candidates = |cipher_text: &CipherText| {
+            let solution = Decipher::new(index.clone(), cipher_text).find_solution();
+            assert_eq!(solution.partial_solutions().len(), 1);
+            let mut words: Vec<&str> = solution.partial_solutions()[0].satisfactory_words()[&CipherWordId::from(0)]
+                .ids()
+                .iter()
+                .map(|word_id| index.vocabulary().get(*word_id).unwrap())
+                .collect();
+            words.sort();
+            words.into_iter().map(str::to_string).collect::<Vec<String>>()
+        };
+        assert_eq!(candidates(&cipher_text), vec!["tan", "tin"]);
+        assert_eq!(candidates(&cipher_text.without_self_mapping()), vec!["tin"]);
+    }
+
+    #[test]
+    fn finds_nothing_when_a_free_word_can_only_map_to_itself() {
+        let index = Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new("the\neht\nab".to_string()))));
+        let cipher_text = CipherText::new("xyz zyx ab".to_string());
+        assert_eq!(Decipher::new(index.clone(), &cipher_text).find_solution().partial_solutions().len(), 2);
+        let cipher_text = cipher_text.without_self_mapping();
+        assert!(Decipher::new(index, &cipher_text).find_solution().partial_solutions().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use cipher_text::{CipherText, CipherWordId};
+use decipher::PartialSolution;
+use vocabulary::{Vocabulary, WordId};
+
+const ALPHABET_LENGTH: usize = 26;
+
+/// Complete reading of a cipher text: a vocabulary word for every cipher word,
+/// equal cipher letters read as equal plain letters and different cipher
+/// letters as different plain letters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decryption {
+    words: Vec<WordId>,
+    plain_by_cipher: [Option<u8>; ALPHABET_LENGTH],
+}
+
+struct Enumeration<'r> {
+    cipher_words: Vec<&'r [u8]>,
+    candidates: Vec<&'r [WordId]>,
+    order: Vec<usize>,
+    vocabulary: &'r Vocabulary,
+    words: Vec<Option<WordId>>,
+    plain_by_cipher: [Option<u8>; ALPHABET_LENGTH],
+    cipher_by_plain: [Option<u8>; ALPHABET_LENGTH],
+    no_self_mapping: bool,
+    limit: usize,
+    found: Vec<Decryption>,
+}
+
+/// Lists up to `limit` decryptions which use candidates of `partial_solution`
+/// and agree with hints of `cipher_text`. Cipher words missing from the partial
+/// solution may be any vocabulary word of their length. Letters other than the
+/// hinted ones never read as themselves if `cipher_text` forbids self-mapping.
+pub fn decryptions(
+    cipher_text: &CipherText,
+    vocabulary: &Vocabulary,
+    partial_solution: &PartialSolution,
+    limit: usize,
+) -> Vec<Decryption> {
+    let cipher_words: Vec<&[u8]> = cipher_text.words().into_iter().map(str::as_bytes).collect();
+    let candidates: Vec<&[WordId]> = cipher_words
+        .iter()
+        .enumerate()
+        .map(|(index, cipher_word)| {
+            match partial_solution.satisfactory_words().get(&CipherWordId::from(index)) {
+                Some(words) => words.ids(),
+                None => vocabulary
+                    .by_length()
+                    .get(cipher_word.len())
+                    .map(Vec::as_slice)
+                    .unwrap_or(&[]),
+            }
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..cipher_words.len()).collect();
+    order.sort_by_key(|index| candidates[*index].len());
+
+    let mut enumeration = Enumeration {
+        words: vec![None; cipher_words.len()],
+        cipher_words,
+        candidates,
+        order,
+        vocabulary,
+        plain_by_cipher: [None; ALPHABET_LENGTH],
+        cipher_by_plain: [None; ALPHABET_LENGTH],
+        no_self_mapping: cipher_text.forbids_self_mapping(),
+        limit,
+        found: Vec::new(),
+    };
+    for hint in cipher_text.hints() {
+        enumeration.plain_by_cipher[letter_index(hint.cipher as u8)] = Some(hint.plain.0);
+        enumeration.cipher_by_plain[letter_index(hint.plain.0)] = Some(hint.cipher as u8);
+    }
+    enumeration.run(0);
+    enumeration.found
+}
+
+impl Decryption {
+    pub fn words(&self) -> &[WordId] {
+        &self.words
+    }
+
+    pub fn plain_of(&self, cipher: char) -> Option<char> {
+        if !cipher.is_ascii_lowercase() {
+            return None;
+        }
+        self.plain_by_cipher[letter_index(cipher as u8)].map(char::from)
+    }
+
+    pub fn text(&self, vocabulary: &Vocabulary) -> String {
+        let words: Vec<&str> = self.words
+            .iter()
+            .map(|word_id| vocabulary.get(*word_id).unwrap())
+            .collect();
+        words.join(" ")
+    }
+}
+
+impl<'r> Enumeration<'r> {
+    fn run(&mut self, depth: usize) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if depth == self.order.len() {
+            self.found.push(Decryption {
+                words: self.words.iter().map(|word| word.unwrap()).collect(),
+                plain_by_cipher: self.plain_by_cipher,
+            });
+            return;
+        }
+        let word_index = self.order[depth];
+        let cipher_word = self.cipher_words[word_index];
+        for &word_id in self.candidates[word_index] {
+            let plain_word = self.vocabulary.get(word_id).unwrap().as_bytes();
+            let mut assigned = Vec::with_capacity(cipher_word.len());
+            if self.assign(cipher_word, plain_word, &mut assigned) {
+                self.words[word_index] = Some(word_id);
+                self.run(depth + 1);
+                self.words[word_index] = None;
+            }
+            for (cipher, plain) in assigned {
+                self.plain_by_cipher[cipher] = None;
+                self.cipher_by_plain[plain] = None;
+            }
+            if self.found.len() >= self.limit {
+                return;
+            }
+        }
+    }
+
+    /// Extends the key with letters of `plain_word`, remembering new entries in
+    /// `assigned` so that they can be undone.
+    fn assign(&mut self, cipher_word: &[u8], plain_word: &[u8], assigned: &mut Vec<(usize, usize)>) -> bool {
+        for (cipher, plain) in cipher_word.iter().zip(plain_word) {
+            let (cipher, plain) = (letter_index(*cipher), letter_index(*plain));
+            match (self.plain_by_cipher[cipher], self.cipher_by_plain[plain]) {
+                (None, None) if self.no_self_mapping && cipher == plain => return false,
+                (None, None) => {
+                    self.plain_by_cipher[cipher] = Some(plain as u8 + b'a');
+                    self.cipher_by_plain[plain] = Some(cipher as u8 + b'a');
+                    assigned.push((cipher, plain));
+                }
+                (Some(known_plain), _) if letter_index(known_plain) == plain => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+fn letter_index(letter: u8) -> usize {
+    (letter - b'a') as usize
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use cipher_text::CipherText;
+use decipher::Decipher;
+use pattern::Pattern;
+use vocabulary_index::VocabularyIndex;
+
+/// Search size at which the search part of the score saturates.
+const MAX_NODES_LOG10: f64 = 6.;
+/// Mean branching factor at which the branching part of the score saturates.
+const MAX_BRANCHING_LOG2: f64 = 4.;
+
+#[derive(Debug, Clone)]
+pub struct Difficulty {
+    pub nodes_explored: u64,
+    /// Branching factor of every condition in search order.
+    pub branching_factors: Vec<f64>,
+    pub mean_branching_factor: f64,
+    /// Cipher words whose repetition pattern matches a single vocabulary word.
+    pub pattern_unique_words: usize,
+    pub word_count: usize,
+    /// Part of the cipher letters covered by conditions, that is repeated.
+    pub letter_coverage: f64,
+    pub solutions: usize,
+    /// False when the search stopped at `decipher::MAX_SOLUTIONS`, the
+    /// statistics then only cover part of the search tree.
+    pub complete: bool,
+    /// From 0 (trivial) to 100.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grade {
+    VeryEasy,
+    Easy,
+    Medium,
+    Hard,
+    VeryHard,
+}
+
+/// Solves `cipher_text` and rates how hard it is from the statistics of the search.
+pub fn rate(index: Arc<VocabularyIndex>, cipher_text: &CipherText) -> Difficulty {
+    let words = cipher_text.words();
+    let pattern_unique_words = words
+        .iter()
+        .filter(|word| index.query(&Pattern::repetition(word)).len() == 1)
+        .count();
+
+    let conditioned: HashSet<char> = cipher_text.conditions().iter().map(|c| c.cipher_char()).collect();
+    let letters: Vec<char> = cipher_text.text().chars().filter(char::is_ascii_lowercase).collect();
+    let letter_coverage = if letters.is_empty() {
+        0.
+    } else {
+        letters.iter().filter(|ch| conditioned.contains(ch)).count() as f64 / letters.len() as f64
+    };
+
+    let solution = Decipher::new(index, cipher_text).find_solution();
+    let statistics = solution.statistics();
+    let branching_factors: Vec<f64> = statistics.conditions.iter().map(|c| c.branching_factor()).collect();
+    let mean_branching_factor = if branching_factors.is_empty() {
+        0.
+    } else {
+        branching_factors.iter().sum::<f64>() / branching_factors.len() as f64
+    };
+
+    let search = ((statistics.nodes_explored as f64 + 1.).log10() / MAX_NODES_LOG10).min(1.);
+    let branching = ((mean_branching_factor + 1.).log2() / MAX_BRANCHING_LOG2).min(1.);
+    let footholds = if words.is_empty() {
+        1.
+    } else {
+        1. - pattern_unique_words as f64 / words.len() as f64
+    };
+    let score = 100. * (0.4 * search + 0.2 * branching + 0.2 * footholds + 0.2 * (1. - letter_coverage));
+
+    Difficulty {
+        nodes_explored: statistics.nodes_explored,
+        branching_factors,
+        mean_branching_factor,
+        pattern_unique_words,
+        word_count: words.len(),
+        letter_coverage,
+        solutions: solution.partial_solutions().len(),
+        complete: solution.is_complete(),
+        score,
+    }
+}
+
+impl Difficulty {
+    pub fn grade(&self) -> Grade {
+        match self.score {
+            s if s < 20. => Grade::VeryEasy,
+            s if s < 40. => Grade::Easy,
+            s if s < 60. => Grade::Medium,
+            s if s < 80. => Grade::Hard,
+            _ => Grade::VeryHard,
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let grade = match *self {
+            Grade::VeryEasy => "very easy",
+            Grade::Easy => "easy",
+            Grade::Medium => "medium",
+            Grade::Hard => "hard",
+            Grade::VeryHard => "very hard",
+        };
+        write!(f, "{}", grade)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vocabulary::Vocabulary;
+
+    const WORDS: &str = "the\ncat\nsat\nhat\nmat\nrat\nbat\nten\ntan\nhen\nman\nmen\nnet\nnot\nhot\ndot\ntea\nate\neat\nsea\n\
+                         seat\nheat\nmeat\nneat\nteam\ntame\nsame\nname\nmane\nhate\nmate\nrate\nnote\ntone\nhose\nnose";
+
+    fn index() -> Arc<VocabularyIndex> {
+        Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new(WORDS.to_string()))))
+    }
+
+    fn rate_with(hints: &[&str]) -> Difficulty {
+        let hints = hints.iter().map(|hint| hint.parse().unwrap()).collect();
+        rate(index(), &CipherText::try_with_hints("abc dbe fcab".to_string(), hints).unwrap())
+    }
+
+    #[test]
+    fn rates_lower_with_more_hints() {
+        let without = rate_with(&[]);
+        let one = rate_with(&["a=t"]);
+        let two = rate_with(&["a=t", "b=e"]);
+        assert!(without.complete && one.complete && two.complete);
+        assert!(without.score > one.score, "{} > {}", without.score, one.score);
+        assert!(one.score > two.score, "{} > {}", one.score, two.score);
+        assert!(without.nodes_explored > two.nodes_explored);
+        assert!(without.solutions >= two.solutions);
+    }
+
+    #[test]
+    fn counts_text_statistics() {
+        let difficulty = rate_with(&[]);
+        assert_eq!(difficulty.word_count, 3);
+        assert_eq!(difficulty.letter_coverage, 7. / 10.);
+        assert!(difficulty.score >= 0. && difficulty.score <= 100.);
+    }
+
+    #[test]
+    fn grades_scores() {
+        let grade = |score| Difficulty { score, ..rate_with(&[]) }.grade();
+        assert_eq!(grade(0.), Grade::VeryEasy);
+        assert_eq!(grade(20.), Grade::Easy);
+        assert_eq!(grade(59.9), Grade::Medium);
+        assert_eq!(grade(79.), Grade::Hard);
+        assert_eq!(grade(100.), Grade::VeryHard);
+        assert_eq!(Grade::VeryHard.to_string(), "very hard");
+    }
+}
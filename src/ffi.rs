@@ -0,0 +1,296 @@
+//! C interface to the solver. Every `*_new`, `*_load` and `ws_solve` result
+//! is owned by the caller and released with the matching `*_free` function,
+//! strings returned by the library are released with `ws_string_free`.
+//! Handles passed in must come from these functions and not be freed yet,
+//! strings passed in must be NUL-terminated UTF-8, NULL is always accepted.
+//! A panic never unwinds into the caller, the function returns NULL or 0.
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::Read;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use cipher_text::{CipherText, CipherWordId, Hint};
+use decipher::{Decipher, Solution};
+use render::Render;
+use vocabulary::{Filter, Source, Vocabulary};
+use vocabulary_index::{VocabularyIndex, Words};
+
+pub struct WsVocabulary(Arc<Vocabulary>);
+
+pub struct WsIndex(Arc<VocabularyIndex>);
+
+pub struct WsCipher(CipherText);
+
+pub struct WsSolution {
+    solution: Solution,
+    vocabulary: Arc<Vocabulary>,
+    cipher: CipherText,
+}
+
+/// Reads a vocabulary file with a word per line, NULL if it can't be read.
+#[no_mangle]
+pub unsafe extern "C" fn ws_vocabulary_load(path: *const c_char) -> *mut WsVocabulary {
+    ws_vocabulary_load_filtered(path, 0, 0, ptr::null(), ptr::null(), 0)
+}
+
+/// Same as `ws_vocabulary_load`, but leaves out words shorter than
+/// `min_length`, longer than `max_length`, matching the regular expression
+/// `exclude`, listed in the file `blacklist` or with a known frequency below
+/// `min_frequency`, like the filter flags of the command line tool. 0 or NULL
+/// leaves a rule out, NULL is returned if `exclude` or `blacklist` is invalid.
+#[no_mangle]
+pub unsafe extern "C" fn ws_vocabulary_load_filtered(
+    path: *const c_char,
+    min_length: usize,
+    max_length: usize,
+    exclude: *const c_char,
+    blacklist: *const c_char,
+    min_frequency: u64,
+) -> *mut WsVocabulary {
+    guard(ptr::null_mut(), || {
+        let path = match to_str(path) {
+            Some(path) => path,
+            None => return ptr::null_mut(),
+        };
+        let exclude = match to_str(exclude).map(Regex::new) {
+            Some(Ok(exclude)) => Some(exclude),
+            Some(Err(_)) => return ptr::null_mut(),
+            None => None,
+        };
+        let mut blacklist_text = String::new();
+        if let Some(blacklist) = to_str(blacklist) {
+            if File::open(blacklist).and_then(|mut file| file.read_to_string(&mut blacklist_text)).is_err() {
+                return ptr::null_mut();
+            }
+        }
+        let filter = Filter {
+            min_length: Some(min_length).filter(|&length| length > 0),
+            max_length: Some(max_length).filter(|&length| length > 0),
+            exclude,
+            blacklist: Filter::parse_blacklist(&blacklist_text),
+            min_frequency: Some(min_frequency).filter(|&frequency| frequency > 0),
+        };
+        let mut text = String::new();
+        match File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+            Ok(_) => {
+                let vocabulary = Vocabulary::merge_filtered(vec![(Source::new(String::new(), 1.), text)], filter);
+                Box::into_raw(Box::new(WsVocabulary(Arc::new(vocabulary))))
+            }
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Vocabulary from words separated by new lines.
+#[no_mangle]
+pub unsafe extern "C" fn ws_vocabulary_from_text(text: *const c_char) -> *mut WsVocabulary {
+    guard(ptr::null_mut(), || match to_str(text) {
+        Some(text) => Box::into_raw(Box::new(WsVocabulary(Arc::new(Vocabulary::new(text.to_string()))))),
+        None => ptr::null_mut(),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_vocabulary_free(vocabulary: *mut WsVocabulary) {
+    guard((), || {
+        if !vocabulary.is_null() {
+            drop(Box::from_raw(vocabulary));
+        }
+    })
+}
+
+/// Indexes `vocabulary`, which may be freed afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ws_index_new(vocabulary: *const WsVocabulary) -> *mut WsIndex {
+    guard(ptr::null_mut(), || match vocabulary.as_ref() {
+        Some(vocabulary) => Box::into_raw(Box::new(WsIndex(Arc::new(VocabularyIndex::new(vocabulary.0.clone()))))),
+        None => ptr::null_mut(),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_index_free(index: *mut WsIndex) {
+    guard((), || {
+        if !index.is_null() {
+            drop(Box::from_raw(index));
+        }
+    })
+}
+
+/// Cipher text with optional hints like `"q=e r=t"`, NULL if a hint is
+/// invalid or the text has too many or too long words.
+#[no_mangle]
+pub unsafe extern "C" fn ws_cipher_new(text: *const c_char, hints: *const c_char) -> *mut WsCipher {
+    guard(ptr::null_mut(), || {
+        let text = match to_str(text) {
+            Some(text) => text,
+            None => return ptr::null_mut(),
+        };
+        let hints: Result<Vec<Hint>, String> = match to_str(hints) {
+            Some(hints) => hints.split_whitespace().map(str::parse).collect(),
+            None => Ok(Vec::new()),
+        };
+        match hints.map(|hints| CipherText::try_with_hints(text.to_string(), hints)) {
+            Ok(Ok(cipher)) => Box::into_raw(Box::new(WsCipher(cipher))),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_cipher_free(cipher: *mut WsCipher) {
+    guard((), || {
+        if !cipher.is_null() {
+            drop(Box::from_raw(cipher));
+        }
+    })
+}
+
+/// Runs `Decipher::find_solution` and ranks the partial solutions, best first.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solve(index: *const WsIndex, cipher: *const WsCipher) -> *mut WsSolution {
+    guard(ptr::null_mut(), || {
+        let (index, cipher) = match (index.as_ref(), cipher.as_ref()) {
+            (Some(index), Some(cipher)) => (index, cipher),
+            _ => return ptr::null_mut(),
+        };
+        let vocabulary = index.0.vocabulary().clone();
+        let mut solution = Decipher::new(index.0.clone(), &cipher.0).find_solution();
+        solution.rank(&vocabulary);
+        Box::into_raw(Box::new(WsSolution {
+            solution,
+            vocabulary,
+            cipher: cipher.0.clone(),
+        }))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_free(solution: *mut WsSolution) {
+    guard((), || {
+        if !solution.is_null() {
+            drop(Box::from_raw(solution));
+        }
+    })
+}
+
+/// Number of partial solutions.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_len(solution: *const WsSolution) -> usize {
+    guard(0, || solution.as_ref().map_or(0, |solution| solution.solution.partial_solutions().len()))
+}
+
+/// True when the search tried every letter, false when it stopped at the
+/// solution limit and more partial solutions may exist.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_is_complete(solution: *const WsSolution) -> bool {
+    guard(false, || solution.as_ref().is_some_and(|solution| solution.solution.is_complete()))
+}
+
+/// Number of cipher words, the same for every partial solution.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_word_count(solution: *const WsSolution) -> usize {
+    guard(0, || solution.as_ref().map_or(0, |solution| solution.cipher.words().len()))
+}
+
+/// Number of candidates of cipher word `word` in partial solution `index`,
+/// 0 when the word is unconstrained and may be any word of its length.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_candidates_len(solution: *const WsSolution, index: usize, word: usize) -> usize {
+    guard(0, || {
+        solution
+            .as_ref()
+            .and_then(|solution| solution.candidates(index, word))
+            .map_or(0, |words| words.len())
+    })
+}
+
+/// Candidate `candidate` of cipher word `word` in partial solution `index`,
+/// NULL when out of range.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_candidate(
+    solution: *const WsSolution,
+    index: usize,
+    word: usize,
+    candidate: usize,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let solution = match solution.as_ref() {
+            Some(solution) => solution,
+            None => return ptr::null_mut(),
+        };
+        solution
+            .candidates(index, word)
+            .and_then(|words| words.ids().get(candidate))
+            .and_then(|word_id| solution.vocabulary.get(*word_id))
+            .map_or(ptr::null_mut(), to_c_string)
+    })
+}
+
+/// Text rendering of all partial solutions, as printed by the command line tool.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_render(solution: *const WsSolution) -> *mut c_char {
+    guard(ptr::null_mut(), || match solution.as_ref() {
+        Some(solution) => to_c_string(&solution.render().to_string()),
+        None => ptr::null_mut(),
+    })
+}
+
+/// JSON rendering of all partial solutions, see `Render::json`.
+#[no_mangle]
+pub unsafe extern "C" fn ws_solution_json(solution: *const WsSolution) -> *mut c_char {
+    guard(ptr::null_mut(), || match solution.as_ref() {
+        Some(solution) => to_c_string(&solution.render().json().to_string()),
+        None => ptr::null_mut(),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ws_string_free(string: *mut c_char) {
+    guard((), || {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    })
+}
+
+impl WsSolution {
+    fn render(&self) -> Render<'_> {
+        Render::new(&self.solution, self.vocabulary.clone(), &self.cipher)
+    }
+
+    /// None when `index` or `word` is out of range or the word is unconstrained.
+    fn candidates(&self, index: usize, word: usize) -> Option<&Words> {
+        if word >= self.cipher.words().len() {
+            return None;
+        }
+        self.solution
+            .partial_solutions()
+            .get(index)
+            .and_then(|partial_solution| partial_solution.satisfactory_words().get(&CipherWordId::from(word)))
+    }
+}
+
+/// Runs `body`, returning `default` if it panics.
+fn guard<T, F: FnOnce() -> T>(default: T, body: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(default)
+}
+
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        None
+    } else {
+        CStr::from_ptr(string).to_str().ok()
+    }
+}
+
+fn to_c_string(string: &str) -> *mut c_char {
+    CString::new(string).map_or(ptr::null_mut(), CString::into_raw)
+}
@@ -0,0 +1,462 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Deepest nesting of arrays and objects accepted by the parser, deeper
+/// input is rejected before it can exhaust the stack.
+pub const MAX_DEPTH: usize = 64;
+
+/// Minimal JSON value, `Display` writes it without whitespace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Value of field `name` of an object.
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => fields.iter().find(|field| field.0 == name).map(|field| &field.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Number value if it is a non-negative integer.
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(value) if value >= 0. && value.fract() == 0. => Some(value as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JsonError {
+    UnexpectedEnd,
+    UnexpectedChar(char, usize),
+    InvalidNumber(usize),
+    InvalidEscape(usize),
+    TrailingCharacters(usize),
+    TooDeep(usize),
+}
+
+struct Parser<'r> {
+    text: &'r str,
+    position: usize,
+    /// Arrays and objects open at `position`.
+    depth: usize,
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(value: &'a str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Json {
+        Json::String(value)
+    }
+}
+
+impl From<char> for Json {
+    fn from(value: char) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Json {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Json {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Json {
+        Json::Number(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map(Into::into).unwrap_or(Json::Null)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Json {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) if !value.is_finite() => write!(f, "null"),
+            Json::Number(value) if value.fract() == 0. && value.abs() < 1e15 => write!(f, "{}", value as i64),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(ref value) => write_string(f, value),
+            Json::Array(ref values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref fields) => {
+                write!(f, "{{")?;
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, value: &str) -> Result<(), fmt::Error> {
+    write!(f, "\"")?;
+    for ch in value.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl FromStr for Json {
+    type Err = JsonError;
+
+    fn from_str(text: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser {
+            text,
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(JsonError::TrailingCharacters(parser.position));
+        }
+        Ok(value)
+    }
+}
+
+impl<'r> Parser<'r> {
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(JsonError::UnexpectedEnd),
+            Some('{') => self.nested(Parser::object),
+            Some('[') => self.nested(Parser::array),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            Some(ch) => Err(JsonError::UnexpectedChar(ch, self.position)),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Parser<'r>) -> Result<Json, JsonError>) -> Result<Json, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(JsonError::TooDeep(self.position));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((name, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(fields)),
+                Some(ch) => return Err(JsonError::UnexpectedChar(ch, self.position - 1)),
+                None => return Err(JsonError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(values)),
+                Some(ch) => return Err(JsonError::UnexpectedChar(ch, self.position - 1)),
+                None => return Err(JsonError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None => return Err(JsonError::UnexpectedEnd),
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let escape = self.position;
+                    let ch = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let code = self.text
+                                .get(self.position..self.position + 4)
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .ok_or(JsonError::InvalidEscape(escape))?;
+                            self.position += 4;
+                            ::std::char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        _ => return Err(JsonError::InvalidEscape(escape)),
+                    };
+                    value.push(ch);
+                }
+                Some(ch) => value.push(ch),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_digit() || "+-.eE".contains(ch) {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        self.text[start..self.position]
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| JsonError::InvalidNumber(start))
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, JsonError> {
+        if self.text[self.position..].starts_with(keyword) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(JsonError::UnexpectedChar(self.peek().unwrap(), self.position))
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.next() {
+            Some(ch) if ch == expected => Ok(()),
+            Some(ch) => Err(JsonError::UnexpectedChar(ch, self.position - ch.len_utf8())),
+            None => Err(JsonError::UnexpectedEnd),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek() {
+            if ch.is_whitespace() {
+                self.position += ch.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position += ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            JsonError::UnexpectedEnd => write!(f, "unexpected end of JSON"),
+            JsonError::UnexpectedChar(ch, position) => write!(f, "unexpected '{}' at {}", ch, position),
+            JsonError::InvalidNumber(position) => write!(f, "invalid number at {}", position),
+            JsonError::InvalidEscape(position) => write!(f, "invalid escape at {}", position),
+            JsonError::TrailingCharacters(position) => write!(f, "unexpected characters after JSON at {}", position),
+            JsonError::TooDeep(position) => write!(f, "nested deeper than {} at {}", MAX_DEPTH, position),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_compact_json() {
+        let value = Json::object(vec![
+            ("word", "cat".into()),
+            ("count", 3usize.into()),
+            ("score", 0.5.into()),
+            ("complete", true.into()),
+            ("hint", Json::from(None::<char>)),
+            ("letters", vec!['a', 'b'].into()),
+            ("huge", f64::INFINITY.into()),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"{"word":"cat","count":3,"score":0.5,"complete":true,"hint":null,"letters":["a","b"],"huge":null}"#
+        );
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let value = Json::from("say \"hi\"\\\n\t\u{1}");
+        assert_eq!(value.to_string(), r#""say \"hi\"\\\n\t\u0001""#);
+        assert_eq!(value.to_string().parse::<Json>(), Ok(value));
+    }
+
+    #[test]
+    fn parses_what_was_written() {
+        let value = Json::object(vec![
+            ("text", "qak xskjq".into()),
+            ("hints", Json::object(vec![("q", "t".into())])),
+            ("max", 100usize.into()),
+            ("ratio", (-1.25).into()),
+            ("words", Json::Array(vec![Json::Null, false.into(), Json::Array(vec![])])),
+            ("empty", Json::object(vec![])),
+        ]);
+        assert_eq!(value.to_string().parse::<Json>(), Ok(value));
+    }
+
+    #[test]
+    fn parses_whitespace_and_escapes() {
+        let value: Json = " { \"a\" : [ 1 , 2e2 ] , \"b\" : \"\\u00e9\\/\" } ".parse().unwrap();
+        assert_eq!(value.get("a").and_then(Json::as_array).map(|values| values.len()), Some(2));
+        assert_eq!(value.get("a").unwrap().as_array().unwrap()[1].as_usize(), Some(200));
+        assert_eq!(value.get("b").and_then(Json::as_str), Some("é/"));
+        assert_eq!(value.get("c"), None);
+    }
+
+    #[test]
+    fn reads_numbers() {
+        assert_eq!(Json::Number(3.).as_usize(), Some(3));
+        assert_eq!(Json::Number(3.5).as_usize(), None);
+        assert_eq!(Json::Number(-1.).as_usize(), None);
+        assert_eq!(Json::Number(3.5).as_f64(), Some(3.5));
+        assert_eq!(Json::from("3").as_usize(), None);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!("".parse::<Json>(), Err(JsonError::UnexpectedEnd));
+        assert_eq!("[1, 2".parse::<Json>(), Err(JsonError::UnexpectedEnd));
+        assert_eq!("{\"a\" 1}".parse::<Json>(), Err(JsonError::UnexpectedChar('1', 5)));
+        assert_eq!("[1 2]".parse::<Json>(), Err(JsonError::UnexpectedChar('2', 3)));
+        assert_eq!("tru".parse::<Json>(), Err(JsonError::UnexpectedChar('t', 0)));
+        assert_eq!("-".parse::<Json>(), Err(JsonError::InvalidNumber(0)));
+        assert_eq!("\"\\x\"".parse::<Json>(), Err(JsonError::InvalidEscape(2)));
+        assert_eq!("\"\\u12\"".parse::<Json>(), Err(JsonError::InvalidEscape(2)));
+        assert_eq!("null x".parse::<Json>(), Err(JsonError::TrailingCharacters(5)));
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(nested(MAX_DEPTH).parse::<Json>().is_ok());
+        assert_eq!(nested(MAX_DEPTH + 1).parse::<Json>(), Err(JsonError::TooDeep(MAX_DEPTH)));
+        assert_eq!("[".repeat(200_000).parse::<Json>(), Err(JsonError::TooDeep(MAX_DEPTH)));
+
+        let objects = format!("{}1{}", "{\"a\":".repeat(MAX_DEPTH + 1), "}".repeat(MAX_DEPTH + 1));
+        assert_eq!(objects.parse::<Json>(), Err(JsonError::TooDeep(5 * MAX_DEPTH)));
+    }
+}
@@ -0,0 +1,284 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const ALPHABET_LENGTH: usize = 26;
+
+/// Simple substitution key, maps every plain letter to a cipher letter.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    cipher_by_plain: [u8; ALPHABET_LENGTH],
+}
+
+/// Keyed alphabet conventions of the American Cryptogram Association: in K1 the
+/// plain alphabet starts with the keyword, in K2 the cipher alphabet does and
+/// in K3 both do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyedAlphabet {
+    K1,
+    K2,
+    K3,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum KeyError {
+    EmptyKeyword,
+    NoShiftWithoutSelfMapping,
+}
+
+impl Key {
+    /// Random key where no letter maps to itself.
+    pub fn random(seed: u64) -> Key {
+        let mut random = XorShift::new(seed);
+        let mut cipher_by_plain = straight_alphabet();
+        loop {
+            for index in (1..ALPHABET_LENGTH).rev() {
+                let other = (random.next() % (index as u64 + 1)) as usize;
+                cipher_by_plain.swap(index, other);
+            }
+            let key = Key { cipher_by_plain };
+            if !key.has_self_mapping() {
+                return key;
+            }
+        }
+    }
+
+    /// Key with a keyword-mixed alphabet slid by `shift` against the straight one.
+    /// Without `shift` the smallest one that leaves no letter mapped to itself is used.
+    pub fn keyed(alphabet: KeyedAlphabet, keyword: &str, shift: Option<usize>) -> Result<Key, KeyError> {
+        let mixed = mixed_alphabet(keyword).ok_or(KeyError::EmptyKeyword)?;
+        let key_with_shift = |shift: usize| Key::mixed(alphabet, &mixed, shift);
+        match shift {
+            Some(shift) => Ok(key_with_shift(shift)),
+            None => (0..ALPHABET_LENGTH)
+                .map(key_with_shift)
+                .find(|key| !key.has_self_mapping())
+                .ok_or(KeyError::NoShiftWithoutSelfMapping),
+        }
+    }
+
+    /// Key of the `alphabet` convention with the `mixed` alphabet slid by `shift`.
+    pub(crate) fn mixed(alphabet: KeyedAlphabet, mixed: &[u8], shift: usize) -> Key {
+        let mut cipher_by_plain = [0; ALPHABET_LENGTH];
+        for (index, letter) in mixed.iter().enumerate() {
+            let shifted = (index + shift) % ALPHABET_LENGTH;
+            let straight = b'a' + shifted as u8;
+            match alphabet {
+                KeyedAlphabet::K1 => cipher_by_plain[(letter - b'a') as usize] = straight,
+                KeyedAlphabet::K2 => cipher_by_plain[(straight - b'a') as usize] = *letter,
+                KeyedAlphabet::K3 => cipher_by_plain[(letter - b'a') as usize] = mixed[shifted],
+            }
+        }
+        Key { cipher_by_plain }
+    }
+
+    pub fn has_self_mapping(&self) -> bool {
+        self.cipher_by_plain
+            .iter()
+            .enumerate()
+            .any(|(plain, cipher)| *cipher == b'a' + plain as u8)
+    }
+
+    pub fn encrypt_char(&self, plain: char) -> char {
+        if plain.is_ascii_lowercase() {
+            self.cipher_by_plain[(plain as u8 - b'a') as usize] as char
+        } else {
+            plain
+        }
+    }
+
+    pub fn encrypt(&self, text: &str) -> String {
+        text.chars().map(|ch| self.encrypt_char(ch)).collect()
+    }
+
+    pub fn decrypt_char(&self, cipher: char) -> char {
+        match self.cipher_by_plain.iter().position(|letter| *letter as char == cipher) {
+            Some(plain) => (b'a' + plain as u8) as char,
+            None => cipher,
+        }
+    }
+
+    pub fn decrypt(&self, text: &str) -> String {
+        text.chars().map(|ch| self.decrypt_char(ch)).collect()
+    }
+}
+
+impl FromStr for KeyedAlphabet {
+    type Err = String;
+
+    fn from_str(alphabet: &str) -> Result<KeyedAlphabet, String> {
+        match alphabet {
+            "k1" | "K1" => Ok(KeyedAlphabet::K1),
+            "k2" | "K2" => Ok(KeyedAlphabet::K2),
+            "k3" | "K3" => Ok(KeyedAlphabet::K3),
+            _ => Err(format!("unknown alphabet {}, expected k1, k2 or k3", alphabet)),
+        }
+    }
+}
+
+pub(crate) fn straight_alphabet() -> [u8; ALPHABET_LENGTH] {
+    let mut alphabet = [0; ALPHABET_LENGTH];
+    for (index, letter) in alphabet.iter_mut().enumerate() {
+        *letter = b'a' + index as u8;
+    }
+    alphabet
+}
+
+/// Keyword letters without repeats followed by the rest of the alphabet.
+pub(crate) fn mixed_alphabet(keyword: &str) -> Option<Vec<u8>> {
+    let mut mixed = Vec::with_capacity(ALPHABET_LENGTH);
+    for letter in keyword.to_lowercase().bytes().filter(u8::is_ascii_lowercase) {
+        if !mixed.contains(&letter) {
+            mixed.push(letter);
+        }
+    }
+    if mixed.is_empty() {
+        return None;
+    }
+    for letter in straight_alphabet().iter() {
+        if !mixed.contains(letter) {
+            mixed.push(*letter);
+        }
+    }
+    Some(mixed)
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> XorShift {
+        XorShift(seed ^ 0x9e37_79b9_7f4a_7c15 | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let plain: String = straight_alphabet().iter().map(|letter| *letter as char).collect();
+        let cipher: String = self.cipher_by_plain.iter().map(|letter| *letter as char).collect();
+        write!(f, "plain:  {}\ncipher: {}", plain, cipher)
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let cipher: String = self.cipher_by_plain.iter().map(|letter| *letter as char).collect();
+        write!(f, "Key {{ {} }}", cipher)
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            KeyError::EmptyKeyword => write!(f, "keyword has no letters"),
+            KeyError::NoShiftWithoutSelfMapping => {
+                write!(f, "every shift of this keyword maps some letter to itself")
+            }
+        }
+    }
+}
+
+impl Error for KeyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRAIGHT: &str = "abcdefghijklmnopqrstuvwxyz";
+    const MIXED: &str = "zebrastipcdfghjklmnoquvwxy";
+
+    fn rotated(alphabet: &str, shift: usize) -> String {
+        format!("{}{}", &alphabet[shift..], &alphabet[..shift])
+    }
+
+    #[test]
+    fn mixes_the_alphabet_with_a_keyword() {
+        assert_eq!(mixed_alphabet("Zebra Stripes!"), Some(MIXED.as_bytes().to_vec()));
+        assert_eq!(mixed_alphabet("123"), None);
+        assert_eq!(&straight_alphabet()[..], STRAIGHT.as_bytes());
+    }
+
+    #[test]
+    fn derives_k1_keys() {
+        // The plain alphabet starts with the keyword.
+        let key = Key::keyed(KeyedAlphabet::K1, "zebra stripes", Some(0)).unwrap();
+        assert_eq!(key.encrypt(MIXED), STRAIGHT);
+        let key = Key::keyed(KeyedAlphabet::K1, "zebra stripes", Some(3)).unwrap();
+        assert_eq!(key.encrypt(MIXED), rotated(STRAIGHT, 3));
+    }
+
+    #[test]
+    fn derives_k2_keys() {
+        // The cipher alphabet starts with the keyword.
+        let key = Key::keyed(KeyedAlphabet::K2, "zebra stripes", Some(0)).unwrap();
+        assert_eq!(key.encrypt(STRAIGHT), MIXED);
+        let key = Key::keyed(KeyedAlphabet::K2, "zebra stripes", Some(5)).unwrap();
+        assert_eq!(key.encrypt(&rotated(STRAIGHT, 5)), MIXED);
+    }
+
+    #[test]
+    fn derives_k3_keys() {
+        // Both alphabets start with the keyword.
+        let key = Key::keyed(KeyedAlphabet::K3, "zebra stripes", Some(4)).unwrap();
+        assert_eq!(key.encrypt(MIXED), rotated(MIXED, 4));
+        assert!(!key.has_self_mapping());
+    }
+
+    #[test]
+    fn picks_the_first_shift_without_self_mapping() {
+        for &alphabet in &[KeyedAlphabet::K1, KeyedAlphabet::K2, KeyedAlphabet::K3] {
+            let key = Key::keyed(alphabet, "abc", None).unwrap();
+            assert_eq!(key, Key::keyed(alphabet, "abc", Some(1)).unwrap());
+            assert!(!key.has_self_mapping());
+            assert!(Key::keyed(alphabet, "abc", Some(0)).unwrap().has_self_mapping());
+        }
+        assert_eq!(
+            Key::keyed(KeyedAlphabet::K2, "zebra", None),
+            Key::keyed(KeyedAlphabet::K2, "zebra", Some(0))
+        );
+        assert_eq!(Key::keyed(KeyedAlphabet::K1, "", None), Err(KeyError::EmptyKeyword));
+    }
+
+    #[test]
+    fn decrypts_what_was_encrypted() {
+        let text = "the quick brown fox, jumps over the lazy dog!";
+        let keys = vec![
+            Key::random(7),
+            Key::keyed(KeyedAlphabet::K1, "zebra", None).unwrap(),
+            Key::keyed(KeyedAlphabet::K2, "zebra", Some(11)).unwrap(),
+            Key::keyed(KeyedAlphabet::K3, "zebra", Some(25)).unwrap(),
+        ];
+        for key in keys {
+            let encrypted = key.encrypt(text);
+            assert_ne!(encrypted, text);
+            assert_eq!(key.decrypt(&encrypted), text, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn makes_random_keys_without_self_mapping() {
+        for seed in 0..100 {
+            let key = Key::random(seed);
+            assert!(!key.has_self_mapping());
+            let mut cipher: Vec<char> = key.encrypt(STRAIGHT).chars().collect();
+            cipher.sort();
+            assert_eq!(cipher.into_iter().collect::<String>(), STRAIGHT);
+        }
+        assert_eq!(Key::random(7), Key::random(7));
+        assert_ne!(Key::random(7), Key::random(8));
+    }
+
+    #[test]
+    fn parses_alphabets() {
+        assert_eq!("K1".parse(), Ok(KeyedAlphabet::K1));
+        assert_eq!("k2".parse(), Ok(KeyedAlphabet::K2));
+        assert_eq!("k3".parse(), Ok(KeyedAlphabet::K3));
+        assert!("k4".parse::<KeyedAlphabet>().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use key::{self, Key, KeyedAlphabet};
+use vocabulary::{Char, Vocabulary, WordId};
+
+const ALPHABET_LENGTH: usize = 26;
+/// Shortest vocabulary word tried as a keyword.
+const MIN_KEYWORD_LENGTH: usize = 3;
+
+/// Keyword, alphabet convention and shift which give a key agreeing with the
+/// known letters.
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    pub alphabet: KeyedAlphabet,
+    pub keyword: WordId,
+    pub shift: usize,
+    pub key: Key,
+}
+
+/// Tries every vocabulary word as a K1, K2 and K3 keyword with every shift,
+/// except K3 without a shift which is the identity key, and keeps the keys
+/// which map every cipher letter of `known` to its plain letter. Keywords
+/// giving the same key are listed once, the longest and most frequent keyword
+/// first. Nothing is recovered without known letters.
+pub fn recover(vocabulary: &Vocabulary, known: &[(char, Char)]) -> Vec<Recovery> {
+    let known: Vec<(u8, u8)> = known
+        .iter()
+        .filter(|&&(cipher, _)| cipher.is_ascii_lowercase())
+        .map(|&(cipher, plain)| (cipher as u8, plain.0))
+        .collect();
+    if known.is_empty() {
+        return Vec::new();
+    }
+    let straight = key::straight_alphabet();
+    let mut found: Vec<Recovery> = Vec::new();
+    for words in vocabulary.by_length().iter().skip(MIN_KEYWORD_LENGTH) {
+        for &word_id in words {
+            let mixed = match key::mixed_alphabet(vocabulary.get(word_id).unwrap()) {
+                Some(ref mixed) if mixed[..] == straight[..] => continue,
+                Some(mixed) => mixed,
+                None => continue,
+            };
+            for &alphabet in &[KeyedAlphabet::K1, KeyedAlphabet::K2, KeyedAlphabet::K3] {
+                for shift in 0..ALPHABET_LENGTH {
+                    // The same mixed alphabet on both sides without a shift
+                    // leaves every letter as it is, whatever the keyword.
+                    if alphabet == KeyedAlphabet::K3 && shift == 0 {
+                        continue;
+                    }
+                    let key = Key::mixed(alphabet, &mixed, shift);
+                    if known
+                        .iter()
+                        .all(|&(cipher, plain)| key.encrypt_char(plain as char) == cipher as char)
+                    {
+                        found.push(Recovery {
+                            alphabet,
+                            keyword: word_id,
+                            shift,
+                            key,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    found.sort_by(|left, right| {
+        let by_length = vocabulary.get(right.keyword).unwrap().len().cmp(&vocabulary.get(left.keyword).unwrap().len());
+        let by_frequency = vocabulary.frequency(right.keyword).cmp(&vocabulary.frequency(left.keyword));
+        let by_weight = vocabulary
+            .weight(right.keyword)
+            .partial_cmp(&vocabulary.weight(left.keyword))
+            .unwrap_or(Ordering::Equal);
+        by_length.then(by_frequency).then(by_weight)
+    });
+    let mut seen: HashSet<Key> = HashSet::new();
+    found.into_iter().filter(|recovery| seen.insert(recovery.key.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PANGRAM: &str = "the quick brown fox jumps over the lazy dog";
+
+    fn known(key: &Key, plain_text: &str) -> Vec<(char, Char)> {
+        plain_text
+            .chars()
+            .filter(char::is_ascii_lowercase)
+            .map(|plain| (key.encrypt_char(plain), Char::from(plain)))
+            .collect()
+    }
+
+    fn keywords(vocabulary: &Vocabulary, recoveries: &[Recovery]) -> Vec<String> {
+        recoveries
+            .iter()
+            .map(|recovery| vocabulary.get(recovery.keyword).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn recovers_keyword_and_shift() {
+        let vocabulary = Vocabulary::new("zebra\nhorse\ncat".to_string());
+        for &alphabet in &[KeyedAlphabet::K1, KeyedAlphabet::K2, KeyedAlphabet::K3] {
+            let key = Key::keyed(alphabet, "horse", Some(3)).unwrap();
+            let recoveries = recover(&vocabulary, &known(&key, PANGRAM));
+            assert_eq!(recoveries.len(), 1);
+            assert_eq!(keywords(&vocabulary, &recoveries), vec!["horse"]);
+            assert_eq!(recoveries[0].alphabet, alphabet);
+            assert_eq!(recoveries[0].shift, 3);
+            assert_eq!(recoveries[0].key, key);
+        }
+    }
+
+    #[test]
+    fn keeps_every_key_agreeing_with_one_letter() {
+        let vocabulary = Vocabulary::new("zebra\nhorse\ncat".to_string());
+        let key = Key::keyed(KeyedAlphabet::K2, "zebra", Some(5)).unwrap();
+        let recoveries = recover(&vocabulary, &known(&key, "t"));
+        assert!(recoveries.len() > 1);
+        assert!(recoveries.iter().any(|recovery| recovery.key == key));
+        assert!(recoveries
+            .iter()
+            .all(|recovery| recovery.key.encrypt_char('t') == key.encrypt_char('t')));
+    }
+
+    #[test]
+    fn lists_the_longest_keyword_of_a_key() {
+        let vocabulary = Vocabulary::new("bel\nbell".to_string());
+        let key = Key::keyed(KeyedAlphabet::K1, "bel", Some(2)).unwrap();
+        let recoveries = recover(&vocabulary, &known(&key, PANGRAM));
+        assert_eq!(keywords(&vocabulary, &recoveries), vec!["bell"]);
+    }
+
+    #[test]
+    fn skips_the_identity_key() {
+        let vocabulary = Vocabulary::new("zebra\nhorse\ncat".to_string());
+        let identity: Vec<(char, Char)> = PANGRAM
+            .chars()
+            .filter(char::is_ascii_lowercase)
+            .map(|letter| (letter, Char::from(letter)))
+            .collect();
+        assert!(recover(&vocabulary, &identity).is_empty());
+    }
+
+    #[test]
+    fn recovers_nothing_without_known_letters() {
+        let vocabulary = Vocabulary::new("zebra\nhorse\ncat".to_string());
+        assert!(recover(&vocabulary, &[]).is_empty());
+        assert!(recover(&vocabulary, &[('?', Char::from('a'))]).is_empty());
+    }
+}
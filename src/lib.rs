@@ -0,0 +1,24 @@
+extern crate rayon;
+extern crate regex;
+
+pub mod vocabulary;
+pub mod vocabulary_index;
+pub mod cipher_text;
+pub mod decipher;
+pub mod render;
+pub mod cache;
+pub mod pattern;
+pub mod anagram;
+pub mod key;
+pub mod decryption;
+pub mod verify;
+pub mod difficulty;
+pub mod json;
+pub mod trace;
+pub mod session;
+pub mod ffi;
+pub mod affine;
+pub mod keyword;
+pub mod analysis;
+pub mod vocabulary_stats;
+pub mod affix;
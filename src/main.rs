@@ -1,69 +1,635 @@
-#![feature(underscore_lifetimes)]
-#![feature(inclusive_range_syntax)]
 
 extern crate env_logger;
+extern crate flate2;
 #[macro_use]
 extern crate log;
+extern crate regex;
 extern crate structopt;
 #[macro_use]
 extern crate structopt_derive;
+extern crate word_search;
 
+use flate2::bufread::MultiGzDecoder;
+use regex::Regex;
 use structopt::StructOpt;
 
+use std::cmp;
+use std::collections::HashSet;
+use std::env;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[allow(non_local_definitions)]
 mod app;
-mod vocabulary;
-mod vocabulary_index;
-mod cipher_text;
-mod decipher;
-mod render;
-
-use app::App;
-use vocabulary::Vocabulary;
-use vocabulary_index::VocabularyIndex;
-use cipher_text::CipherText;
-use decipher::Decipher;
-use render::Render;
+mod progress_bar;
+mod repl;
+mod server;
+
+use app::{App, ColorMode, Command, FilterOptions, Mode, OutputFormat, SourceSpec};
+use progress_bar::ProgressBar;
+use word_search::vocabulary::{Char, Filter, Source, Vocabulary, MAX_SOURCES};
+use word_search::vocabulary_index::VocabularyIndex;
+use word_search::cipher_text::{self, CipherText, Hint};
+use word_search::verify::{self, Uniqueness};
+use word_search::decipher::Decipher;
+use word_search::render::Render;
+use word_search::cache;
+use word_search::anagram::AnagramIndex;
+use word_search::key::Key;
+use word_search::keyword;
+use word_search::affine::{self, AffineKey};
+use word_search::affix::{self, Affixes};
+use word_search::analysis;
+use word_search::vocabulary_stats;
+use word_search::difficulty::{self, Difficulty};
+use word_search::trace;
+use word_search::json::Json;
+
+/// Best keys printed in shift and affine modes.
+const SHOWN_KEYS: usize = 5;
+/// First bytes of a gzip file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 fn main() {
     env_logger::init();
-    let app = App::from_args();
+    let matches = App::clap().args(&FilterOptions::args()).get_matches();
+    let filter_options = FilterOptions::from_matches(&matches);
+    let app = App::from_clap(matches);
 
-    if app.chipher_text.is_empty() {
-        println!("No cipher provided!");
+    if let Some(command) = app.command {
+        run_command(command, &filter_options);
         return;
     }
 
-    let vocabulary_name = app.vocabulary;
-    let mut file = File::open(vocabulary_name).unwrap();
-    let mut vocabulary = String::new();
-    file.read_to_string(&mut vocabulary).unwrap();
-
-    let words: Vec<_> = vocabulary.lines().collect();
-
-    let vocabulary = Vocabulary::new(&words);
-    debug!("{:?}", vocabulary);
+    let (vocabulary, chipher_text) = match (app.vocabulary, app.chipher_text) {
+        (Some(vocabulary), Some(chipher_text)) if !chipher_text.is_empty() => {
+            (vocabulary, chipher_text)
+        }
+        _ => {
+            println!("No cipher provided!");
+            return;
+        }
+    };
 
-    let index = VocabularyIndex::new(&vocabulary);
+    let index = Arc::new(read_index(&vocabulary, &app.extra_vocabularies, &filter_options));
+    debug!("{:?}", index.vocabulary());
     debug!("{:#?}", index);
 
-    let mut cipher_text = CipherText::new(app.chipher_text);
+    let mut cipher_text = match CipherText::try_with_hints(chipher_text, app.hints) {
+        Ok(cipher_text) => cipher_text,
+        Err(err) => fail(app.format, &format!("Invalid cipher text: {}", err)),
+    };
+    if app.no_self_mapping {
+        cipher_text = cipher_text.without_self_mapping();
+    }
     debug!("{:#?}", &cipher_text);
 
-    println!("Current conditions: {}", &cipher_text);
-    println!("Reorder?");
-    if let Some(reorder) = reorder() {
-        cipher_text.reorder_conditions(&reorder);
-        println!("Reordered: {}", &cipher_text);
+    match app.mode {
+        Mode::Shift | Mode::Affine => {
+            let keys = if app.mode == Mode::Shift {
+                AffineKey::shifts()
+            } else {
+                AffineKey::all()
+            };
+            let candidates = affine::solve(&index, cipher_text.text(), &keys);
+            match app.format {
+                OutputFormat::Text => {
+                    for candidate in candidates.iter().take(SHOWN_KEYS) {
+                        println!("{:3.0}% {:<12} {}", candidate.score * 100., candidate.key, candidate.plain_text);
+                    }
+                }
+                OutputFormat::Json => println!("{}", affine_json(candidates.iter().take(SHOWN_KEYS))),
+            }
+            return;
+        }
+        Mode::Auto => match affine::detect(&index, cipher_text.text()) {
+            Some(candidate) => {
+                match app.format {
+                    OutputFormat::Text => println!("{}: {}", candidate.key, candidate.plain_text),
+                    OutputFormat::Json => println!("{}", affine_json(::std::iter::once(&candidate))),
+                }
+                return;
+            }
+            None if app.format == OutputFormat::Text => {
+                println!("Not a shift or affine cipher, solving as substitution")
+            }
+            None => {}
+        },
+        Mode::Substitution => {}
+    }
+
+    if app.format == OutputFormat::Text {
+        println!("Current conditions: {}", &cipher_text);
+        println!("Reorder?");
+        if let Some(reorder) = reorder() {
+            cipher_text.reorder_conditions(&reorder);
+            println!("Reordered: {}", &cipher_text);
+        }
     }
 
-    let solution = Decipher::new(index, &cipher_text).find_solution();
+    let decipher = Decipher::new(index.clone(), &cipher_text);
+    let mut solution = match (app.progress, app.threads) {
+        _ if app.trace.is_some() => {
+            let (path, format) = (app.trace.unwrap(), app.trace_format);
+            let traced = File::create(&path)
+                .and_then(|file| trace::trace(index.clone(), &cipher_text, format, BufWriter::new(file)));
+            match traced {
+                Ok(solution) => solution,
+                Err(err) => fail(app.format, &format!("Failed to write trace {}: {}", path, err)),
+            }
+        }
+        (true, threads) => {
+            let mut progress_bar = ProgressBar::new();
+            let solution = match threads {
+                Some(threads) => decipher
+                    .find_solution_parallel_with_progress(threads, |progress| progress_bar.update(progress)),
+                None => decipher.find_solution_with_progress(|progress| progress_bar.update(progress)),
+            };
+            progress_bar.finish();
+            solution
+        }
+        (false, Some(threads)) => decipher.find_solution_parallel(threads),
+        (false, None) => decipher.find_solution(),
+    };
     debug!("{:?}", solution);
+    if !solution.is_complete() && app.format == OutputFormat::Text {
+        println!("Too many solutions!");
+        ::std::process::exit(1);
+    }
+    solution.rank(index.vocabulary());
+
+    let render = Render::new(&solution, index.vocabulary().clone(), &cipher_text)
+        .with_layout(app.layout, app.show)
+        .with_color(colored(app.color));
+    match app.format {
+        OutputFormat::Text => println!("{}", render),
+        OutputFormat::Json => println!("{}", render.json()),
+    }
+    // JSON output lists the solutions found so far with `complete` set to false.
+    if !solution.is_complete() {
+        ::std::process::exit(1);
+    }
+}
+
+/// Prints `message` as text or as a JSON object `{"error": "..."}` and exits.
+fn fail(format: OutputFormat, message: &str) -> ! {
+    match format {
+        OutputFormat::Text => println!("{}", message),
+        OutputFormat::Json => println!("{}", Json::object(vec![("error", message.into())])),
+    }
+    ::std::process::exit(1);
+}
+
+/// `{"keys": [...]}` with `Candidate::json` of every key, best first.
+fn affine_json<'a, I: Iterator<Item = &'a affine::Candidate>>(candidates: I) -> Json {
+    Json::object(vec![("keys", candidates.map(affine::Candidate::json).collect::<Vec<Json>>().into())])
+}
+
+fn run_command(command: Command, filter_options: &FilterOptions) {
+    match command {
+        Command::BuildCache {
+            vocabulary,
+            output,
+            extra_vocabularies,
+        } => {
+            let index = read_index(&vocabulary, &extra_vocabularies, filter_options);
+            let mut sources: Vec<String> = Vec::new();
+            for source in ::std::iter::once(&vocabulary).chain(&extra_vocabularies) {
+                sources.push(source.path.clone());
+                if is_dic(&source.path) {
+                    sources.push(affix_path(&source.path));
+                }
+            }
+            if let Err(err) = cache::save(&output, &sources, &index) {
+                println!("Failed to write cache: {}", err);
+                ::std::process::exit(1);
+            }
+        }
+        Command::Query {
+            vocabulary,
+            pattern,
+            extra_vocabularies,
+            limit,
+        } => {
+            let index = read_index(&vocabulary, &extra_vocabularies, filter_options);
+            let found = index.query(&pattern);
+            for word_id in found.iter().take(limit.unwrap_or(found.len())) {
+                println!("{}", index.vocabulary().get(*word_id).unwrap());
+            }
+        }
+        Command::Anagram {
+            vocabulary,
+            letters,
+            extra_vocabularies,
+            sub,
+            words,
+            limit,
+        } => {
+            let index = read_index(&vocabulary, &extra_vocabularies, filter_options);
+            let anagrams = AnagramIndex::new(index.vocabulary().clone());
+            let vocabulary = anagrams.vocabulary();
+            let letters = letters.to_lowercase();
+            let limit = limit.unwrap_or(usize::MAX);
+            if let Some(words) = words {
+                for phrase in anagrams.multi_word_anagrams(&letters, words, limit) {
+                    let groups: Vec<String> = phrase
+                        .iter()
+                        .map(|group| {
+                            let group: Vec<&str> = group.iter().map(|id| vocabulary.get(*id).unwrap()).collect();
+                            group.join("/")
+                        })
+                        .collect();
+                    println!("{}", groups.join(" "));
+                }
+            } else {
+                let found = if sub {
+                    anagrams.sub_anagrams(&letters)
+                } else {
+                    anagrams.anagrams(&letters).to_vec()
+                };
+                for word_id in found.iter().take(limit) {
+                    println!("{}", vocabulary.get(*word_id).unwrap());
+                }
+            }
+        }
+        Command::Encrypt {
+            text,
+            keyword,
+            alphabet,
+            shift,
+            seed,
+            print_key,
+        } => {
+            let key = match keyword {
+                Some(keyword) => match Key::keyed(alphabet, &keyword, shift) {
+                    Ok(key) => key,
+                    Err(err) => {
+                        println!("Can't make key: {}", err);
+                        ::std::process::exit(1);
+                    }
+                },
+                None => Key::random(seed.unwrap_or_else(time_seed)),
+            };
+            if key.has_self_mapping() {
+                println!("Warning: key maps some letters to themselves");
+            }
+            println!("{}", key.encrypt(&cipher_text::normalize(&text)));
+            if print_key {
+                println!("{}", key);
+            }
+        }
+        Command::Verify {
+            vocabulary,
+            chipher_text,
+            extra_vocabularies,
+            hints,
+            plain,
+        } => {
+            let index = Arc::new(read_index(&vocabulary, &extra_vocabularies, filter_options));
+            let mut cipher_text = read_cipher_text(chipher_text, hints);
+            if let Some(plain) = plain {
+                match verify::generate_hints(index.clone(), &cipher_text, &plain) {
+                    Ok(hints) => {
+                        let listed: Vec<String> = hints.iter().map(Hint::to_string).collect();
+                        println!("Hints: {}", listed.join(" "));
+                        cipher_text = CipherText::with_hints(cipher_text.text().to_string(), hints);
+                    }
+                    Err(err) => {
+                        println!("Can't generate hints: {}", err);
+                        ::std::process::exit(1);
+                    }
+                }
+            }
+            match verify::verify(index.clone(), &cipher_text) {
+                Uniqueness::NoSolution => println!("No solution"),
+                Uniqueness::Truncated => {
+                    println!("Too many solutions, can't tell if the solution is unique");
+                    ::std::process::exit(1);
+                }
+                Uniqueness::Unique(only) => {
+                    println!("Unique solution: {}", only.text(index.vocabulary()))
+                }
+                Uniqueness::Ambiguous(first, second) => {
+                    println!("Ambiguous, at least two solutions:");
+                    println!("    {}", first.text(index.vocabulary()));
+                    println!("    {}", second.text(index.vocabulary()));
+                    ::std::process::exit(1);
+                }
+            }
+        }
+        Command::Rate {
+            vocabulary,
+            chipher_texts,
+            extra_vocabularies,
+        } => {
+            let index = Arc::new(read_index(&vocabulary, &extra_vocabularies, filter_options));
+            let mut rated: Vec<(Difficulty, String)> = chipher_texts
+                .into_iter()
+                .filter_map(|chipher_text| match CipherText::try_with_hints(chipher_text, Vec::new()) {
+                    Ok(cipher_text) => Some(cipher_text),
+                    Err(err) => {
+                        println!("Invalid cipher text: {}", err);
+                        None
+                    }
+                })
+                .map(|cipher_text| (difficulty::rate(index.clone(), &cipher_text), cipher_text.text().to_string()))
+                .collect();
+            rated.sort_by(|left, right| left.0.score.partial_cmp(&right.0.score).unwrap_or(cmp::Ordering::Equal));
+            for (difficulty, text) in rated {
+                println!("{:5.1} {:<9} {}", difficulty.score, difficulty.grade(), text);
+                println!(
+                    "      nodes: {}, mean branching: {:.2}, pattern-unique words: {}/{}, letter coverage: {:.0}%, solutions: {}",
+                    difficulty.nodes_explored,
+                    difficulty.mean_branching_factor,
+                    difficulty.pattern_unique_words,
+                    difficulty.word_count,
+                    difficulty.letter_coverage * 100.,
+                    difficulty.solutions
+                );
+                if !difficulty.complete {
+                    println!("      search stopped after too many solutions, the score is a lower bound");
+                }
+            }
+        }
+        Command::Analyze {
+            vocabulary,
+            chipher_text,
+            extra_vocabularies,
+            hints,
+        } => {
+            let index = read_index(&vocabulary, &extra_vocabularies, filter_options);
+            let cipher_text = read_cipher_text(chipher_text, hints);
+            print!("{}", analysis::analyze(&index, &cipher_text));
+        }
+        Command::VocabStats { vocabularies } => {
+            let mut sources = Vec::with_capacity(vocabularies.len());
+            for path in vocabularies {
+                let mut bytes = Vec::new();
+                if let Err(err) = open_file(&path).and_then(|mut reader| reader.read_to_end(&mut bytes)) {
+                    println!("Can't read {}: {}", path, err);
+                    ::std::process::exit(1);
+                }
+                if cache::is_cache(&bytes) {
+                    println!("{} is a vocabulary cache, statistics need the word list", path);
+                    ::std::process::exit(1);
+                }
+                if is_dic(&path) {
+                    sources.extend(expand_dic(&path, 1., &bytes[..]));
+                } else {
+                    let text = String::from_utf8_lossy(&bytes).into_owned();
+                    sources.push((Source::new(path, 1.), text));
+                }
+            }
+            print!("{}", vocabulary_stats::check(&sources, read_filter(filter_options)));
+        }
+        Command::Serve {
+            vocabulary,
+            extra_vocabularies,
+            address,
+            time_limit,
+            max_solutions,
+        } => {
+            let index = Arc::new(read_index(&vocabulary, &extra_vocabularies, filter_options));
+            let limits = server::Limits {
+                time_limit: Duration::from_millis(time_limit as u64),
+                max_solutions,
+            };
+            if let Err(err) = server::run(index, &address, limits) {
+                println!("Failed to serve on {}: {}", address, err);
+                ::std::process::exit(1);
+            }
+        }
+        Command::Keyword {
+            vocabulary,
+            chipher_text,
+            extra_vocabularies,
+            hints,
+            limit,
+        } => {
+            let index = Arc::new(read_index(&vocabulary, &extra_vocabularies, filter_options));
+            let mut known: Vec<(char, Char)> = hints.iter().map(|hint| (hint.cipher, hint.plain)).collect();
+            let cipher_text = chipher_text.map(|text| read_cipher_text(text, hints));
+            if let Some(ref cipher_text) = cipher_text {
+                let mut solution = Decipher::new(index.clone(), cipher_text).find_solution();
+                if !solution.is_complete() {
+                    println!("Too many solutions, add hints to recover a keyword");
+                    ::std::process::exit(1);
+                }
+                solution.rank(index.vocabulary());
+                if let Some(best) = solution.partial_solutions().first() {
+                    known = best.key(cipher_text, index.vocabulary());
+                }
+                let mut unknown: Vec<char> = cipher_text
+                    .text()
+                    .chars()
+                    .filter(|ch| ch.is_ascii_lowercase() && !known.iter().any(|&(cipher, _)| cipher == *ch))
+                    .collect();
+                unknown.sort();
+                unknown.dedup();
+                if !unknown.is_empty() {
+                    let unknown: String = unknown.into_iter().collect();
+                    println!("Plain letters of {} are unknown, the recovered keys fill them in", unknown);
+                }
+            }
+            let recovered = keyword::recover(index.vocabulary(), &known);
+            println!("{} keys fit {} known letters", recovered.len(), known.len());
+            for recovery in recovered.iter().take(limit) {
+                println!(
+                    "{:?} {} shift {}",
+                    recovery.alphabet,
+                    index.vocabulary().get(recovery.keyword).unwrap(),
+                    recovery.shift
+                );
+                if let Some(ref cipher_text) = cipher_text {
+                    println!("    {}", recovery.key.decrypt(cipher_text.text()));
+                }
+            }
+        }
+        Command::Repl {
+            vocabulary,
+            chipher_text,
+            extra_vocabularies,
+        } => {
+            let index = Arc::new(read_index(&vocabulary, &extra_vocabularies, filter_options));
+            repl::run(index, chipher_text);
+        }
+    }
+}
+
+/// In auto mode colors are used on a terminal unless the `NO_COLOR`
+/// environment variable is set to anything but an empty string.
+fn colored(mode: ColorMode) -> bool {
+    match mode {
+        ColorMode::Never => false,
+        ColorMode::Always => true,
+        ColorMode::Auto => {
+            io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+        }
+    }
+}
+
+fn read_cipher_text(text: String, hints: Vec<Hint>) -> CipherText {
+    match CipherText::try_with_hints(text, hints) {
+        Ok(cipher_text) => cipher_text,
+        Err(err) => {
+            println!("Invalid cipher text: {}", err);
+            ::std::process::exit(1);
+        }
+    }
+}
+
+/// Reads either a cache written by `build-cache` which already has the index,
+/// or plain vocabularies which are merged and indexed.
+/// A cache is only used when it was built with the same filter or no filter is given.
+fn read_index(vocabulary: &SourceSpec, extra_vocabularies: &[SourceSpec], filter_options: &FilterOptions) -> VocabularyIndex {
+    let filter = read_filter(filter_options);
+    let mut sources: Vec<(Source, Box<dyn BufRead>)> = Vec::with_capacity(1 + extra_vocabularies.len());
+    for source in ::std::iter::once(vocabulary).chain(extra_vocabularies) {
+        let mut reader = open_file(&source.path).unwrap_or_else(|err| {
+            println!("Can't read {}: {}", source.path, err);
+            ::std::process::exit(1);
+        });
+        let is_cache = reader.fill_buf().map(cache::is_cache).unwrap_or(false);
+
+        if is_cache {
+            if !extra_vocabularies.is_empty() {
+                println!("Can't merge cache {} with other vocabularies", source.path);
+                ::std::process::exit(1);
+            }
+            let mut bytes = Vec::new();
+            if let Err(err) = reader.read_to_end(&mut bytes) {
+                println!("Can't read {}: {}", source.path, err);
+                ::std::process::exit(1);
+            }
+            match cache::load(&bytes) {
+                Ok(ref index) if !filter.is_empty() && *index.vocabulary().filter() != filter => {
+                    println!(
+                        "Cache {} was built with filter: {}, rebuild it to use filter: {}",
+                        source.path,
+                        index.vocabulary().filter(),
+                        filter
+                    );
+                    ::std::process::exit(1);
+                }
+                Ok(index) => return index,
+                Err(err) => {
+                    println!("Can't use cache {}: {}", source.path, err);
+                    ::std::process::exit(1);
+                }
+            }
+        }
+
+        if is_dic(&source.path) {
+            for (source, text) in expand_dic(&source.path, source.weight, reader) {
+                sources.push((source, Box::new(io::Cursor::new(text))));
+            }
+        } else {
+            sources.push((Source::new(source.path.clone(), source.weight), reader));
+        }
+    }
+
+    if sources.len() > MAX_SOURCES {
+        println!("At most {} vocabularies can be merged", MAX_SOURCES);
+        ::std::process::exit(1);
+    }
+    match Vocabulary::read_filtered(sources, filter) {
+        Ok(vocabulary) => VocabularyIndex::new(Arc::new(vocabulary)),
+        Err(err) => {
+            println!("Can't read vocabulary: {}", err);
+            ::std::process::exit(1);
+        }
+    }
+}
+
+/// Opens a file for reading, decompressing it on the way if it starts with
+/// the gzip magic bytes.
+fn open_file(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Stems of a Hunspell dictionary and the forms derived from them with the
+/// affix file next to it, as two sources with words separated by new lines.
+fn expand_dic<R: BufRead>(path: &str, weight: f64, dic: R) -> Vec<(Source, String)> {
+    let affixes = read_affixes(&affix_path(path));
+    let (stems, derived) = affix::expand(dic, &affixes).unwrap_or_else(|err| {
+        println!("Can't read {}: {}", path, err);
+        ::std::process::exit(1);
+    });
+    vec![
+        (Source::new(path.to_string(), weight), stems.join("\n")),
+        (
+            Source::new(format!("{} (derived)", path), weight * affix::DERIVED_WEIGHT),
+            derived.join("\n"),
+        ),
+    ]
+}
+
+/// Hunspell dictionaries end with `.dic`, or `.dic.gz` when compressed.
+fn is_dic(path: &str) -> bool {
+    path.strip_suffix(".gz").unwrap_or(path).ends_with(".dic")
+}
+
+/// Hunspell `.aff` file next to a `.dic` or `.dic.gz` file.
+fn affix_path(dic_path: &str) -> String {
+    let dic_path = dic_path.strip_suffix(".gz").unwrap_or(dic_path);
+    format!("{}.aff", &dic_path[..dic_path.len() - ".dic".len()])
+}
+
+fn read_affixes(path: &str) -> Affixes {
+    let mut bytes = Vec::new();
+    if let Err(err) = open_file(path).and_then(|mut reader| reader.read_to_end(&mut bytes)) {
+        println!("Can't read affix file {}: {}", path, err);
+        ::std::process::exit(1);
+    }
+    match String::from_utf8_lossy(&bytes).parse() {
+        Ok(affixes) => affixes,
+        Err(err) => {
+            println!("Can't use affix file {}: {}", path, err);
+            ::std::process::exit(1);
+        }
+    }
+}
+
+fn read_filter(options: &FilterOptions) -> Filter {
+    let exclude = options.exclude.as_ref().map(|pattern| match Regex::new(pattern) {
+        Ok(exclude) => exclude,
+        Err(err) => {
+            println!("Invalid exclude pattern {}: {}", pattern, err);
+            ::std::process::exit(1);
+        }
+    });
+    let blacklist = match options.blacklist {
+        Some(ref path) => {
+            let mut text = String::new();
+            if let Err(err) = File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+                println!("Can't read blacklist {}: {}", path, err);
+                ::std::process::exit(1);
+            }
+            Filter::parse_blacklist(&text)
+        }
+        None => HashSet::new(),
+    };
+    Filter {
+        min_length: options.min_length,
+        max_length: options.max_length,
+        exclude,
+        blacklist,
+        min_frequency: options.min_frequency,
+    }
+}
 
-    let render = Render::new(solution, &vocabulary, &cipher_text);
-    println!("{}", render);
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() ^ u64::from(duration.subsec_nanos()) << 32)
+        .unwrap_or(0)
 }
 
 fn reorder() -> Option<Vec<usize>> {
@@ -0,0 +1,240 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use vocabulary::{AlphabetIter, Char};
+
+/// Word pattern for `VocabularyIndex::query`. In a pattern `a`-`z` are known
+/// letters, `?` is any letter, `[abc]` and `[^abc]` are letter classes and
+/// `A`-`Z` are variables: every occurrence of a variable is the same letter,
+/// different variables are different letters.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    slots: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Slot {
+    Letter(Char),
+    Any,
+    Class(Vec<Char>),
+    Variable(u8),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PatternError {
+    Empty,
+    TooLong(usize),
+    UnexpectedChar(char),
+    UnclosedClass,
+    EmptyClass,
+}
+
+impl Pattern {
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Pattern of repeated letters of `word`: `error` gives `ABBCB`.
+    pub fn repetition(word: &str) -> Pattern {
+        let mut variables: Vec<char> = Vec::new();
+        let slots = word
+            .chars()
+            .map(|ch| match variables.iter().position(|&variable| variable == ch) {
+                Some(variable) => Slot::Variable(variable as u8),
+                None => {
+                    variables.push(ch);
+                    Slot::Variable(variables.len() as u8 - 1)
+                }
+            })
+            .collect();
+        Pattern { slots }
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        if word.len() != self.slots.len() {
+            return false;
+        }
+        let mut variables: [Option<u8>; 26] = [None; 26];
+        for (slot, ch) in self.slots.iter().zip(word.bytes()) {
+            if !ch.is_ascii_lowercase() {
+                return false;
+            }
+            let matches = match *slot {
+                Slot::Letter(letter) => letter.0 == ch,
+                Slot::Any => true,
+                Slot::Class(ref letters) => letters.contains(&Char(ch)),
+                Slot::Variable(variable) => match variables[variable as usize] {
+                    Some(bound) => bound == ch,
+                    None if variables.contains(&Some(ch)) => false,
+                    None => {
+                        variables[variable as usize] = Some(ch);
+                        true
+                    }
+                },
+            };
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(pattern: &str) -> Result<Pattern, PatternError> {
+        let mut slots = Vec::with_capacity(pattern.len());
+        let mut chars = pattern.chars();
+        while let Some(ch) = chars.next() {
+            let slot = match ch {
+                'a'..='z' => Slot::Letter(Char::from(ch)),
+                'A'..='Z' => Slot::Variable(ch as u8 - b'A'),
+                '?' => Slot::Any,
+                '[' => parse_class(&mut chars)?,
+                _ => return Err(PatternError::UnexpectedChar(ch)),
+            };
+            slots.push(slot);
+        }
+        if slots.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if slots.len() > 255 {
+            return Err(PatternError::TooLong(slots.len()));
+        }
+        Ok(Pattern { slots })
+    }
+}
+
+/// Parses the rest of `[abc]` or `[^abc]` after the opening bracket.
+fn parse_class<I: Iterator<Item = char>>(chars: &mut I) -> Result<Slot, PatternError> {
+    let mut listed = Vec::new();
+    let mut negated = false;
+    let mut first = true;
+    loop {
+        match chars.next() {
+            Some(']') => break,
+            Some('^') if first => negated = true,
+            Some(ch @ 'a'..='z') => listed.push(Char::from(ch)),
+            Some(ch) => return Err(PatternError::UnexpectedChar(ch)),
+            None => return Err(PatternError::UnclosedClass),
+        }
+        first = false;
+    }
+    let letters: Vec<Char> = AlphabetIter::new()
+        .filter(|letter| listed.contains(letter) != negated)
+        .collect();
+    if letters.is_empty() {
+        return Err(PatternError::EmptyClass);
+    }
+    Ok(Slot::Class(letters))
+}
+
+/// Writes the pattern in the syntax `FromStr` reads, negated classes are
+/// written as the letters they match.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for slot in &self.slots {
+            match *slot {
+                Slot::Letter(letter) => write!(f, "{:?}", letter)?,
+                Slot::Any => write!(f, "?")?,
+                Slot::Class(ref letters) => {
+                    write!(f, "[")?;
+                    for letter in letters {
+                        write!(f, "{:?}", letter)?;
+                    }
+                    write!(f, "]")?;
+                }
+                Slot::Variable(variable) => write!(f, "{}", (b'A' + variable) as char)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            PatternError::Empty => write!(f, "pattern is empty"),
+            PatternError::TooLong(length) => write!(f, "pattern of {} letters is too long", length),
+            PatternError::UnexpectedChar(ch) => write!(f, "unexpected '{}' in pattern", ch),
+            PatternError::UnclosedClass => write!(f, "letter class is not closed with ']'"),
+            PatternError::EmptyClass => write!(f, "letter class matches no letter"),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(pattern: &str) -> Pattern {
+        pattern.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_every_kind_of_slot() {
+        let pattern = parse("c?[ae]B");
+        assert_eq!(
+            pattern.slots(),
+            &[
+                Slot::Letter(Char::from('c')),
+                Slot::Any,
+                Slot::Class(vec![Char::from('a'), Char::from('e')]),
+                Slot::Variable(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn negated_class_lists_the_other_letters() {
+        match parse("[^abcdefghijklmnopqrstuvw]").slots()[0] {
+            Slot::Class(ref letters) => assert_eq!(letters, &[Char::from('x'), Char::from('y'), Char::from('z')]),
+            ref slot => panic!("expected a class, got {:?}", slot),
+        }
+        assert_eq!(parse("[^aeiou]?").to_string(), "[bcdfghjklmnpqrstvwxyz]?");
+    }
+
+    #[test]
+    fn reports_invalid_patterns() {
+        assert_eq!("".parse::<Pattern>().unwrap_err(), PatternError::Empty);
+        assert_eq!("c1t".parse::<Pattern>().unwrap_err(), PatternError::UnexpectedChar('1'));
+        assert_eq!("[ab".parse::<Pattern>().unwrap_err(), PatternError::UnclosedClass);
+        assert_eq!("[aB]".parse::<Pattern>().unwrap_err(), PatternError::UnexpectedChar('B'));
+        assert_eq!("[a^]".parse::<Pattern>().unwrap_err(), PatternError::UnexpectedChar('^'));
+        assert_eq!("[]".parse::<Pattern>().unwrap_err(), PatternError::EmptyClass);
+        let long = "?".repeat(256);
+        assert_eq!(long.parse::<Pattern>().unwrap_err(), PatternError::TooLong(256));
+    }
+
+    #[test]
+    fn matches_letters_classes_and_variables() {
+        assert!(parse("c?t").matches("cat"));
+        assert!(!parse("c?t").matches("cart"));
+        assert!(parse("[bc]at").matches("bat"));
+        assert!(!parse("[^bc]at").matches("bat"));
+        assert!(parse("ABBA").matches("abba"));
+        assert!(!parse("ABBA").matches("aaaa"));
+        assert!(!parse("AB").matches("aa"));
+        assert!(!parse("???").matches("Cat"));
+    }
+
+    #[test]
+    fn repetition_round_trips_through_display() {
+        let pattern = Pattern::repetition("error");
+        assert_eq!(pattern.to_string(), "ABBCB");
+        assert!(parse(&pattern.to_string()).matches("error"));
+        assert!(!pattern.matches("sassy"));
+    }
+}
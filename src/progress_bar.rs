@@ -0,0 +1,43 @@
+use std::io::{self, Write};
+
+use word_search::decipher::Progress;
+
+const WIDTH: usize = 40;
+
+pub struct ProgressBar {
+    last_rendered: Option<usize>,
+}
+
+impl ProgressBar {
+    pub fn new() -> ProgressBar {
+        ProgressBar { last_rendered: None }
+    }
+
+    pub fn update(&mut self, progress: &Progress) {
+        let filled = ((progress.fraction_complete * WIDTH as f64) as usize).min(WIDTH);
+        let mut bar = String::with_capacity(WIDTH);
+        for index in 0..WIDTH {
+            bar.push(if index < filled { '#' } else { '-' });
+        }
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = write!(
+            stderr,
+            "\r[{}] {:5.1}% nodes: {} depth: {}/{} solutions: {}",
+            bar,
+            progress.fraction_complete * 100.,
+            progress.nodes_explored,
+            progress.depth,
+            progress.max_depth,
+            progress.solutions_found
+        );
+        let _ = stderr.flush();
+        self.last_rendered = Some(filled);
+    }
+
+    pub fn finish(&mut self) {
+        if self.last_rendered.take().is_some() {
+            eprintln!();
+        }
+    }
+}
@@ -1,13 +1,45 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
-use vocabulary::Vocabulary;
+use vocabulary::{Vocabulary, WordId};
+use vocabulary_index::Words;
 use decipher::Solution;
 use cipher_text::{CipherText, CipherWordId};
+use json::Json;
+use decryption;
 
-pub struct Render<'r, 'a> {
-    solution: Solution,
-    vocabulary: &'r Vocabulary<'r>,
+/// Characters per line of the aligned layout.
+const ALIGNED_WIDTH: usize = 72;
+/// ANSI color codes of the aligned layout.
+const HINTED_COLOR: &str = "1;32";
+const DERIVED_COLOR: &str = "36";
+const UNKNOWN_COLOR: &str = "2;31";
+
+/// Combinations shown per partial solution in the expanded layout without a limit.
+const DEFAULT_EXPANDED_LIMIT: usize = 100;
+
+pub struct Render<'a> {
+    solution: &'a Solution,
+    vocabulary: Arc<Vocabulary>,
     cipher: &'a CipherText,
+    layout: Layout,
+    limit: Option<usize>,
+    colored: bool,
+}
+
+/// How candidates of a partial solution are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Cipher text with the letters known from the key below it.
+    Aligned,
+    /// Candidates of every cipher word in a column.
+    Columns,
+    /// One line per cipher word with its candidates.
+    Compact,
+    /// Every consistent combination of candidates, checked against a full key.
+    Expanded,
 }
 
 struct SolutionsForSingleWord<'r> {
@@ -16,20 +48,146 @@ struct SolutionsForSingleWord<'r> {
     words: Vec<&'r str>,
 }
 
-impl<'r, 'a> Render<'r, 'a> {
+impl<'a> Render<'a> {
     pub fn new(
-        solution: Solution,
-        vocabulary: &'r Vocabulary,
+        solution: &'a Solution,
+        vocabulary: Arc<Vocabulary>,
         cipher: &'a CipherText,
-    ) -> Render<'r, 'a> {
+    ) -> Render<'a> {
         Render {
             solution,
             vocabulary,
             cipher,
+            layout: Layout::Aligned,
+            limit: None,
+            colored: false,
         }
     }
 
+    /// Highlights hinted, derived and unknown letters of the aligned layout
+    /// with terminal colors.
+    pub fn with_color(mut self, colored: bool) -> Render<'a> {
+        self.colored = colored;
+        self
+    }
+
+    /// Sets the layout of `Display` and the number of candidates per cipher
+    /// word (compact) or combinations per partial solution (expanded) shown.
+    pub fn with_layout(mut self, layout: Layout, limit: Option<usize>) -> Render<'a> {
+        self.layout = layout;
+        self.limit = limit;
+        self
+    }
+
     fn render(&self) -> String {
+        match self.layout {
+            Layout::Aligned => self.render_aligned(),
+            Layout::Columns => self.render_columns(),
+            Layout::Compact => self.render_compact(),
+            Layout::Expanded => self.render_expanded(),
+        }
+    }
+
+    fn render_aligned(&self) -> String {
+        let mut rendered = String::with_capacity(4 * 1024);
+        let hinted: Vec<char> = self.cipher.hints().iter().map(|hint| hint.cipher).collect();
+        for (number, partial_solution) in self.solution.partial_solutions().iter().enumerate() {
+            let key = partial_solution.key(self.cipher, &self.vocabulary);
+            rendered.push_str(&format!(
+                "Solution {}, score {:.2}\n",
+                number + 1,
+                partial_solution.score(&self.vocabulary)
+            ));
+            for line in wrap(self.cipher.text(), ALIGNED_WIDTH) {
+                rendered.push_str(&format!("    {}\n    ", line));
+                for cipher_char in line.chars() {
+                    let plain = key.iter().find(|&&(known, _)| known == cipher_char).map(|&(_, plain)| plain.0 as char);
+                    let (shown, color) = match plain {
+                        _ if cipher_char == ' ' => (' ', None),
+                        Some(plain) if hinted.contains(&cipher_char) => (plain, Some(HINTED_COLOR)),
+                        Some(plain) => (plain, Some(DERIVED_COLOR)),
+                        None => ('_', Some(UNKNOWN_COLOR)),
+                    };
+                    match color {
+                        Some(color) if self.colored => {
+                            rendered.push_str(&format!("\x1b[{}m{}\x1b[0m", color, shown))
+                        }
+                        _ => rendered.push(shown),
+                    }
+                }
+                rendered.push('\n');
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    fn render_compact(&self) -> String {
+        let mut rendered = String::with_capacity(4 * 1024);
+        let cipher_words = self.cipher.words();
+        for (number, partial_solution) in self.solution.partial_solutions().iter().enumerate() {
+            let combinations: f64 = partial_solution
+                .satisfactory_words()
+                .values()
+                .map(|words| words.len() as f64)
+                .product();
+            rendered.push_str(&format!(
+                "Solution {}, score {:.2}, {} combinations at most\n",
+                number + 1,
+                partial_solution.score(&self.vocabulary),
+                combinations
+            ));
+            for (index, cipher_word) in cipher_words.iter().enumerate() {
+                match partial_solution.satisfactory_words().get(&CipherWordId::from(index)) {
+                    Some(words) => {
+                        let word_ids = self.by_weight(words);
+                        let shown = self.limit.unwrap_or(word_ids.len()).min(word_ids.len());
+                        let listed: Vec<&str> = word_ids[..shown]
+                            .iter()
+                            .map(|word_id| self.vocabulary.get(*word_id).unwrap())
+                            .collect();
+                        rendered.push_str(&format!("    {} ({}): {}", cipher_word, word_ids.len(), listed.join(", ")));
+                        if shown < word_ids.len() {
+                            rendered.push_str(&format!(" and {} more", word_ids.len() - shown));
+                        }
+                        rendered.push('\n');
+                    }
+                    None => rendered.push_str(&format!("    {}: any word\n", cipher_word)),
+                }
+            }
+        }
+        rendered
+    }
+
+    fn render_expanded(&self) -> String {
+        let mut rendered = String::with_capacity(4 * 1024);
+        let limit = self.limit.unwrap_or(DEFAULT_EXPANDED_LIMIT);
+        let mut total = 0;
+        for (number, partial_solution) in self.solution.partial_solutions().iter().enumerate() {
+            let mut found = decryption::decryptions(self.cipher, &self.vocabulary, partial_solution, limit + 1);
+            let more = found.len() > limit;
+            found.truncate(limit);
+            total += found.len();
+            if found.is_empty() {
+                continue;
+            }
+            rendered.push_str(&format!(
+                "Solution {}, {}{} combinations\n",
+                number + 1,
+                if more { "more than " } else { "" },
+                found.len()
+            ));
+            for decryption in found {
+                rendered.push_str("    ");
+                rendered.push_str(&decryption.text(&self.vocabulary));
+                rendered.push('\n');
+            }
+        }
+        rendered.push_str(&format!("{} combinations shown\n", total));
+        rendered
+    }
+
+    fn render_columns(&self) -> String {
         let mut rendered = String::with_capacity(4 * 1024);
         for partial_solution in self.solution.partial_solutions() {
             let mut solutions = Vec::with_capacity(10);
@@ -40,8 +198,8 @@ impl<'r, 'a> Render<'r, 'a> {
                     self.cipher.length_of(*cipher_word_id).unwrap(),
                 );
 
-                for word_id in words.ids() {
-                    let word = self.vocabulary.get(*word_id).unwrap();
+                for word_id in self.by_weight(words) {
+                    let word = self.vocabulary.get(word_id).unwrap();
                     for_word.add(word);
                 }
 
@@ -56,7 +214,7 @@ impl<'r, 'a> Render<'r, 'a> {
                     let word = by_cipher_word
                         .words
                         .get(solution_word_index)
-                        .map(|w| *w)
+                        .copied()
                         .unwrap_or(by_cipher_word.empty.as_str());
                     rendered.push_str(word);
                     rendered.push_str(", ");
@@ -64,11 +222,147 @@ impl<'r, 'a> Render<'r, 'a> {
                 rendered.push('\n');
             }
             if max_number_of_words > 1 {
-                rendered.push_str("\n");
+                rendered.push('\n');
             }
         }
         rendered
     }
+
+    /// Solution as JSON:
+    ///
+    /// ```text
+    /// {
+    ///   "cipher_text": "qak xskjq",
+    ///   "hints": [{"cipher": "q", "plain": "t"}],
+    ///   "conditions": [{"cipher": "q", "hint": "t", "occurrences": [{"word": 0, "position": 0}]}],
+    ///   "solutions": [{
+    ///     "score": 2,
+    ///     "key": {"a": "h", "k": "e", "q": "t"},
+    ///     "words": [{"cipher": "qak", "candidates": ["the"]}, {"cipher": "xskjq", "candidates": null}]
+    ///   }],
+    ///   "complete": true
+    /// }
+    /// ```
+    ///
+    /// Candidates are ordered by weight, `null` candidates mean any vocabulary
+    /// word of the same length. `complete` is false when the search stopped
+    /// early and only part of the solutions are listed.
+    pub fn json(&self) -> Json {
+        let hints: Vec<Json> = self.cipher
+            .hints()
+            .iter()
+            .map(|hint| {
+                Json::object(vec![
+                    ("cipher", hint.cipher.into()),
+                    ("plain", (hint.plain.0 as char).into()),
+                ])
+            })
+            .collect();
+        let conditions: Vec<Json> = self.cipher
+            .conditions()
+            .iter()
+            .map(|condition| {
+                let occurrences: Vec<Json> = condition
+                    .equal_chars()
+                    .iter()
+                    .map(|ch| {
+                        Json::object(vec![
+                            ("word", ch.cipher_word_id.index().into()),
+                            ("position", usize::from(ch.position.0).into()),
+                        ])
+                    })
+                    .collect();
+                Json::object(vec![
+                    ("cipher", condition.cipher_char().into()),
+                    ("hint", condition.hint().map(|plain| plain.0 as char).into()),
+                    ("occurrences", occurrences.into()),
+                ])
+            })
+            .collect();
+        let cipher_words = self.cipher.words();
+        let solutions: Vec<Json> = self.solution
+            .partial_solutions()
+            .iter()
+            .map(|partial_solution| {
+                let key: Vec<(String, Json)> = partial_solution
+                    .key(self.cipher, &self.vocabulary)
+                    .into_iter()
+                    .map(|(cipher_char, plain)| (cipher_char.to_string(), (plain.0 as char).into()))
+                    .collect();
+                let words: Vec<Json> = cipher_words
+                    .iter()
+                    .enumerate()
+                    .map(|(index, cipher_word)| {
+                        let candidates = partial_solution
+                            .satisfactory_words()
+                            .get(&CipherWordId::from(index))
+                            .map(|words| {
+                                self.by_weight(words)
+                                    .into_iter()
+                                    .map(|word_id| self.vocabulary.get(word_id).unwrap())
+                                    .collect::<Vec<&str>>()
+                            });
+                        Json::object(vec![("cipher", (*cipher_word).into()), ("candidates", candidates.into())])
+                    })
+                    .collect();
+                Json::object(vec![
+                    ("score", partial_solution.score(&self.vocabulary).into()),
+                    ("key", Json::Object(key)),
+                    ("words", words.into()),
+                ])
+            })
+            .collect();
+        Json::object(vec![
+            ("cipher_text", self.cipher.text().into()),
+            ("hints", hints.into()),
+            ("conditions", conditions.into()),
+            ("solutions", solutions.into()),
+            ("complete", self.solution.is_complete().into()),
+        ])
+    }
+
+    fn by_weight(&self, words: &Words) -> Vec<WordId> {
+        let mut word_ids = words.ids().to_vec();
+        word_ids.sort_by(|left, right| {
+            self.vocabulary
+                .weight(*right)
+                .partial_cmp(&self.vocabulary.weight(*left))
+                .unwrap_or(Ordering::Equal)
+        });
+        word_ids
+    }
+}
+
+/// Splits `text` on spaces into lines of at most `width` characters, longer
+/// words get a line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(::std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(layout: &str) -> Result<Layout, String> {
+        match layout {
+            "aligned" => Ok(Layout::Aligned),
+            "columns" => Ok(Layout::Columns),
+            "compact" => Ok(Layout::Compact),
+            "expanded" => Ok(Layout::Expanded),
+            _ => Err(format!("unknown layout {}, expected aligned, columns, compact or expanded", layout)),
+        }
+    }
 }
 
 impl<'r> SolutionsForSingleWord<'r> {
@@ -93,7 +387,7 @@ impl<'r> SolutionsForSingleWord<'r> {
     }
 }
 
-impl<'r, 'a> fmt::Display for Render<'r, 'a> {
+impl<'a> fmt::Display for Render<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f, "{}", self.render())
     }
@@ -0,0 +1,107 @@
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use word_search::cipher_text::Hint;
+use word_search::session::Session;
+use word_search::vocabulary_index::VocabularyIndex;
+
+/// Candidates listed per cipher word by `words`.
+const SHOWN_CANDIDATES: usize = 10;
+
+const HELP: &str = "\
+load TEXT   start solving a new cipher text
+show        show the cipher text and the letters assigned so far
+words       list candidates of every cipher word
+Q=e ...     assign plain letters to cipher letters
+unset Q ... remove assignments
+undo        revert the last change
+solve       let the solver assign the remaining letters
+help        show this help
+quit        leave";
+
+/// Reads commands from stdin until `quit` or end of input.
+pub fn run(index: Arc<VocabularyIndex>, chipher_text: Option<String>) {
+    let mut session = Session::new(index.clone());
+    if let Some(chipher_text) = chipher_text {
+        match session.load(&chipher_text) {
+            Ok(()) => show(&session),
+            Err(err) => println!("{}", err),
+        }
+    } else {
+        println!("Type help for the list of commands");
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        let line = line.trim();
+        let (command, argument) = match line.find(' ') {
+            Some(space) => (&line[..space], line[space + 1..].trim()),
+            None => (line, ""),
+        };
+        match command {
+            "" => {}
+            "quit" | "exit" => break,
+            "help" => println!("{}", HELP),
+            "load" => match session.load(argument) {
+                Ok(()) => show(&session),
+                Err(err) => println!("{}", err),
+            },
+            _ if session.text().is_empty() => println!("Load a cipher text first"),
+            "show" => show(&session),
+            "words" => {
+                for (cipher_word, words) in session.candidates() {
+                    let listed: Vec<&str> = words
+                        .iter()
+                        .take(SHOWN_CANDIDATES)
+                        .map(|word_id| index.vocabulary().get(*word_id).unwrap())
+                        .collect();
+                    println!("{} ({}): {}", cipher_word, words.len(), listed.join(", "));
+                }
+            }
+            "undo" => {
+                if session.undo() {
+                    show(&session);
+                } else {
+                    println!("Nothing to undo");
+                }
+            }
+            "unset" => {
+                for cipher in argument.split_whitespace() {
+                    let cipher = cipher.to_lowercase().chars().next().unwrap();
+                    if let Err(err) = session.unassign(cipher) {
+                        println!("{}", err);
+                    }
+                }
+                show(&session);
+            }
+            "solve" => match session.solve() {
+                Ok(_) => show(&session),
+                Err(err) => println!("{}", err),
+            },
+            _ if line.contains('=') => {
+                for hint in line.split_whitespace() {
+                    match hint.parse::<Hint>() {
+                        Ok(hint) => if let Err(err) = session.assign(hint) {
+                            println!("{}", err);
+                        },
+                        Err(err) => println!("{}", err),
+                    }
+                }
+                show(&session);
+            }
+            _ => println!("Unknown command {}, type help for the list of commands", command),
+        }
+    }
+}
+
+fn show(session: &Session) {
+    println!("{}", session.text());
+    println!("{}", session.plain_text());
+}
@@ -0,0 +1,449 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use word_search::cipher_text::{CipherText, Hint};
+use word_search::decipher::{Decipher, Flow};
+use word_search::json::Json;
+use word_search::pattern::Pattern;
+use word_search::render::Render;
+use word_search::vocabulary_index::VocabularyIndex;
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 1024 * 1024;
+/// Longest request line or header line accepted, with its line break.
+const MAX_LINE: usize = 8 * 1024;
+/// Most header lines accepted in a request.
+const MAX_HEADERS: usize = 64;
+/// Connections answered at the same time, further connections wait in a
+/// queue of the same size and then in the listen backlog.
+const WORKERS: usize = 8;
+/// How long reading a whole request may take, however slowly the client sends.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a connection may stay silent while the response is written.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Part of the time limit in percent given to the search, the rest is left
+/// for ranking and rendering the solutions.
+const SEARCH_SHARE: u32 = 80;
+
+/// Upper bounds for a single solve request, requests may ask for less.
+/// The time limit covers the search, ranking and rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub time_limit: Duration,
+    pub max_solutions: usize,
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Json,
+}
+
+/// Connection which fails reads with `TimedOut` once `deadline` has passed.
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+/// Serves requests on `address` until the process is stopped, connections
+/// are answered by `WORKERS` threads sharing `index`.
+pub fn run(index: Arc<VocabularyIndex>, address: &str, limits: Limits) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!("Listening on {}", listener.local_addr()?);
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(WORKERS);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let index = index.clone();
+        let receiver = receiver.clone();
+        thread::spawn(move || work(&receiver, &index, limits));
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => sender.send(stream).expect("server workers stopped"),
+            Err(err) => warn!("Failed to accept connection: {}", err),
+        }
+    }
+    Ok(())
+}
+
+fn work(receiver: &Mutex<Receiver<TcpStream>>, index: &Arc<VocabularyIndex>, limits: Limits) {
+    loop {
+        let stream = match receiver.lock().unwrap().recv() {
+            Ok(stream) => stream,
+            Err(_) => return,
+        };
+        if let Err(err) = handle(stream, index, limits) {
+            warn!("Failed to answer request: {}", err);
+        }
+    }
+}
+
+fn handle(stream: TcpStream, index: &Arc<VocabularyIndex>, limits: Limits) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(DeadlineReader {
+        stream: stream.try_clone()?,
+        deadline: Instant::now() + READ_TIMEOUT,
+    });
+    let response = match read_request(&mut reader) {
+        Ok(Ok(request)) => {
+            debug!("{} {} {}", request.method, request.path, request.body);
+            answer(index, &request, limits)
+        }
+        Ok(Err(response)) => response,
+        Err(ref err) if err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock => {
+            Response::error(408, "request was not received in time")
+        }
+        Err(err) => return Err(err),
+    };
+    response.write(stream)
+}
+
+/// Reads the request line, headers and body, answers with an error response
+/// when the request is too large or malformed.
+fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Result<Request, Response>> {
+    let mut request_line = String::new();
+    if !read_line(reader, &mut request_line)? {
+        return Ok(Err(Response::error(414, "request line is too long")));
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    let mut headers = 0;
+    loop {
+        let mut header = String::new();
+        if !read_line(reader, &mut header)? {
+            return Ok(Err(Response::error(431, "request headers are too large")));
+        }
+        if header.trim().is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Ok(Err(Response::error(431, "request headers are too large")));
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].trim().eq_ignore_ascii_case("content-length") {
+                content_length = match header[colon + 1..].trim().parse() {
+                    Ok(content_length) => content_length,
+                    Err(_) => return Ok(Err(Response::error(400, "invalid Content-Length"))),
+                };
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Ok(Err(Response::error(413, "request body is too large")));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+fn answer(index: &Arc<VocabularyIndex>, request: &Request, limits: Limits) -> Response {
+    let body = &request.body;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/solve") => parse(body).and_then(|request| solve(index, &request, limits)),
+        ("POST", "/query") => parse(body).and_then(|request| query(index, &request)),
+        ("GET", "/stats") => Ok(stats(index)),
+        (_, "/solve") | (_, "/query") | (_, "/stats") => Err(Response::error(405, "method not allowed")),
+        _ => Err(Response::error(404, "not found")),
+    }.unwrap_or_else(|response| response)
+}
+
+/// Reads a line of at most `MAX_LINE` bytes, false if it is longer.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<bool> {
+    let read = reader.take(MAX_LINE as u64).read_line(line)?;
+    Ok(read < MAX_LINE || line.ends_with('\n'))
+}
+
+fn parse(body: &str) -> Result<Json, Response> {
+    body.parse::<Json>()
+        .map_err(|err| Response::error(400, &format!("invalid JSON: {}", err)))
+}
+
+/// `{"cipher_text": "...", "hints": ["q=e"], "time_limit_ms": 1000, "max_solutions": 100}`,
+/// answers with the JSON output of `Render` and the time the search took.
+fn solve(index: &Arc<VocabularyIndex>, request: &Json, limits: Limits) -> Result<Response, Response> {
+    let text = request
+        .get("cipher_text")
+        .and_then(Json::as_str)
+        .ok_or_else(|| Response::error(400, "cipher_text is required"))?;
+    let mut hints = Vec::new();
+    if let Some(requested) = request.get("hints") {
+        let requested = requested
+            .as_array()
+            .ok_or_else(|| Response::error(400, "hints must be an array"))?;
+        for hint in requested {
+            let hint = hint.as_str()
+                .ok_or_else(|| Response::error(400, "hint must be a string"))?
+                .parse::<Hint>()
+                .map_err(|err| Response::error(400, &err))?;
+            hints.push(hint);
+        }
+    }
+    let time_limit = match request.get("time_limit_ms").and_then(Json::as_usize) {
+        Some(millis) => limits.time_limit.min(Duration::from_millis(millis as u64)),
+        None => limits.time_limit,
+    };
+    let max_solutions = match request.get("max_solutions").and_then(Json::as_usize) {
+        Some(0) => return Err(Response::error(400, "max_solutions must be positive")),
+        Some(max_solutions) => limits.max_solutions.min(max_solutions),
+        None => limits.max_solutions,
+    };
+
+    let cipher_text = CipherText::try_with_hints(text.to_string(), hints)
+        .map_err(|err| Response::error(400, &err.to_string()))?;
+    let started = Instant::now();
+    let search_limit = time_limit * SEARCH_SHARE / 100;
+    let mut solutions_found = 0;
+    let mut solution = Decipher::new(index.clone(), &cipher_text).find_solution_with(
+        |_| {
+            if started.elapsed() < search_limit {
+                Flow::Continue
+            } else {
+                Flow::Stop
+            }
+        },
+        |_| {
+            solutions_found += 1;
+            if solutions_found < max_solutions && started.elapsed() < search_limit {
+                Flow::Continue
+            } else {
+                Flow::Stop
+            }
+        },
+    );
+    solution.rank(index.vocabulary());
+    let mut body = Render::new(&solution, index.vocabulary().clone(), &cipher_text).json();
+    let elapsed = started.elapsed();
+    if elapsed > time_limit {
+        return Err(Response::error(503, "time limit exceeded"));
+    }
+    if let Json::Object(ref mut fields) = body {
+        fields.push(("elapsed_ms".to_string(), (elapsed.as_millis() as u64).into()));
+    }
+    Ok(Response { status: 200, body })
+}
+
+/// `{"pattern": "c?a?e", "limit": 10}`, answers with matching words, most
+/// frequent first.
+fn query(index: &Arc<VocabularyIndex>, request: &Json) -> Result<Response, Response> {
+    let pattern = request
+        .get("pattern")
+        .and_then(Json::as_str)
+        .ok_or_else(|| Response::error(400, "pattern is required"))?
+        .parse::<Pattern>()
+        .map_err(|err| Response::error(400, &err.to_string()))?;
+    let found = index.query(&pattern);
+    let limit = request.get("limit").and_then(Json::as_usize).unwrap_or(found.len());
+    let words: Vec<&str> = found
+        .iter()
+        .take(limit)
+        .map(|word_id| index.vocabulary().get(*word_id).unwrap())
+        .collect();
+    Ok(Response {
+        status: 200,
+        body: Json::object(vec![("count", found.len().into()), ("words", words.into())]),
+    })
+}
+
+fn stats(index: &Arc<VocabularyIndex>) -> Response {
+    let vocabulary = index.vocabulary();
+    let by_length: Vec<usize> = vocabulary.by_length().iter().map(Vec::len).collect();
+    let sources: Vec<Json> = vocabulary
+        .sources()
+        .iter()
+        .map(|source| Json::object(vec![("name", source.name().into()), ("weight", source.weight().into())]))
+        .collect();
+    Response {
+        status: 200,
+        body: Json::object(vec![
+            ("words", by_length.iter().sum::<usize>().into()),
+            ("by_length", by_length.into()),
+            ("sources", sources.into()),
+            ("filter", vocabulary.filter().to_string().into()),
+        ]),
+    }
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "request deadline passed"));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: Json::object(vec![("error", message.into())]),
+        }
+    }
+
+    fn write(&self, mut stream: TcpStream) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            413 => "Payload Too Large",
+            414 => "URI Too Long",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Error",
+        };
+        let body = self.body.to_string();
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+    use word_search::vocabulary::Vocabulary;
+
+    fn request(text: &str) -> Result<Request, Response> {
+        read_request(&mut text.as_bytes()).unwrap()
+    }
+
+    fn status(text: &str) -> u16 {
+        request(text).unwrap_err().status
+    }
+
+    fn index() -> Arc<VocabularyIndex> {
+        Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new("the\ncat\ndog".to_string()))))
+    }
+
+    fn limits() -> Limits {
+        Limits {
+            time_limit: Duration::from_secs(10),
+            max_solutions: 100,
+        }
+    }
+
+    fn answer_to(method: &str, path: &str, body: &str) -> Response {
+        let request = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.to_string(),
+        };
+        answer(&index(), &request, limits())
+    }
+
+    #[test]
+    fn reads_requests() {
+        let read = request("POST /solve HTTP/1.1\r\nHost: localhost\r\ncontent-length: 7\r\n\r\n{\"a\":1}trailing").unwrap();
+        assert_eq!(read.method, "POST");
+        assert_eq!(read.path, "/solve");
+        assert_eq!(read.body, "{\"a\":1}");
+
+        let read = request("GET /stats HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((read.method.as_str(), read.path.as_str(), read.body.as_str()), ("GET", "/stats", ""));
+    }
+
+    #[test]
+    fn rejects_invalid_content_length() {
+        assert_eq!(status("POST /solve HTTP/1.1\r\nContent-Length: seven\r\n\r\n{}"), 400);
+        assert_eq!(status("POST /solve HTTP/1.1\r\nContent-Length: -1\r\n\r\n{}"), 400);
+        assert_eq!(status("POST /solve HTTP/1.1\r\nContent-Length:\r\n\r\n{}"), 400);
+    }
+
+    #[test]
+    fn rejects_large_requests() {
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(status(&long_line), 414);
+
+        let long_header = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(status(&long_header), 431);
+
+        let many_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "X: a\r\n".repeat(MAX_HEADERS + 1));
+        assert_eq!(status(&many_headers), 431);
+        let enough_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "X: a\r\n".repeat(MAX_HEADERS));
+        assert!(request(&enough_headers).is_ok());
+
+        let large_body = format!("POST /solve HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert_eq!(status(&large_body), 413);
+    }
+
+    #[test]
+    fn fails_on_a_short_body() {
+        let err = read_request(&mut &b"POST /solve HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn stops_reading_slow_clients_at_the_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        client.write_all(b"POST /solve HTTP/1.1\r\n").unwrap();
+
+        let started = Instant::now();
+        let mut reader = BufReader::new(DeadlineReader {
+            stream,
+            deadline: started + Duration::from_millis(200),
+        });
+        let err = read_request(&mut reader).unwrap_err();
+        assert!(err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn routes_requests() {
+        assert_eq!(answer_to("GET", "/stats", "").status, 200);
+        assert_eq!(answer_to("GET", "/solve", "").status, 405);
+        assert_eq!(answer_to("POST", "/stats", "").status, 405);
+        assert_eq!(answer_to("GET", "/", "").status, 404);
+        assert_eq!(answer_to("POST", "/solve", "{").status, 400);
+        assert_eq!(answer_to("POST", "/solve", &"[".repeat(200_000)).status, 400);
+    }
+
+    #[test]
+    fn answers_solve_and_query() {
+        let solved = answer_to("POST", "/solve", r#"{"cipher_text": "xli gex"}"#);
+        assert_eq!(solved.status, 200);
+        assert_eq!(solved.body.get("complete"), Some(&Json::Bool(true)));
+        assert_eq!(solved.body.get("solutions").and_then(Json::as_array).map(<[Json]>::len), Some(1));
+
+        assert_eq!(answer_to("POST", "/solve", r#"{"hints": []}"#).status, 400);
+        assert_eq!(answer_to("POST", "/solve", r#"{"cipher_text": "xli", "hints": ["q=e"]}"#).status, 400);
+        assert_eq!(answer_to("POST", "/solve", r#"{"cipher_text": "xli", "max_solutions": 0}"#).status, 400);
+
+        let queried = answer_to("POST", "/query", r#"{"pattern": "?a?"}"#);
+        assert_eq!(queried.status, 200);
+        assert_eq!(queried.body.to_string(), r#"{"count":1,"words":["cat"]}"#);
+    }
+}
@@ -0,0 +1,339 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use cipher_text::{self, CipherText, CipherTextError, Hint};
+use decipher::{Decipher, Flow, PartialSolution};
+use decryption::{self, Decryption};
+use pattern::Pattern;
+use vocabulary::{Char, WordId};
+use vocabulary_index::VocabularyIndex;
+
+/// Solutions of the search ranked by `Session::solve`.
+const SOLVE_LIMIT: usize = 1000;
+
+/// Interactive solving state: a cipher text and the letters assigned so far,
+/// with the history of assignments for undo.
+pub struct Session {
+    index: Arc<VocabularyIndex>,
+    text: String,
+    hints: Vec<Hint>,
+    history: Vec<Vec<Hint>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SessionError {
+    NotInText(char),
+    PlainTaken { plain: char, cipher: char },
+    NotAssigned(char),
+    NoSolution,
+    InvalidText(CipherTextError),
+}
+
+impl Session {
+    pub fn new(index: Arc<VocabularyIndex>) -> Session {
+        Session {
+            index,
+            text: String::new(),
+            hints: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Starts over with a new cipher text, the current one is kept when the
+    /// new one can't be searched.
+    pub fn load(&mut self, text: &str) -> Result<(), SessionError> {
+        let text = cipher_text::normalize(text);
+        CipherText::try_with_hints(text.clone(), Vec::new()).map_err(SessionError::InvalidText)?;
+        self.text = text;
+        self.hints.clear();
+        self.history.clear();
+        Ok(())
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
+    /// The text was checked by `load` and `assign` keeps the hints consistent.
+    pub fn cipher_text(&self) -> CipherText {
+        CipherText::with_hints(self.text.clone(), self.hints.clone())
+    }
+
+    /// Assigns the plain letter of `hint` to its cipher letter, replacing
+    /// an earlier assignment of the same cipher letter.
+    pub fn assign(&mut self, hint: Hint) -> Result<(), SessionError> {
+        if !self.text.contains(hint.cipher) {
+            return Err(SessionError::NotInText(hint.cipher));
+        }
+        if let Some(taken) = self.hints
+            .iter()
+            .find(|known| known.plain == hint.plain && known.cipher != hint.cipher)
+        {
+            return Err(SessionError::PlainTaken {
+                plain: hint.plain.0 as char,
+                cipher: taken.cipher,
+            });
+        }
+        let mut hints: Vec<Hint> = self.hints.iter().filter(|known| known.cipher != hint.cipher).cloned().collect();
+        hints.push(hint);
+        self.replace_hints(hints);
+        Ok(())
+    }
+
+    pub fn unassign(&mut self, cipher: char) -> Result<(), SessionError> {
+        if !self.hints.iter().any(|known| known.cipher == cipher) {
+            return Err(SessionError::NotAssigned(cipher));
+        }
+        let hints = self.hints.iter().filter(|known| known.cipher != cipher).cloned().collect();
+        self.replace_hints(hints);
+        Ok(())
+    }
+
+    /// Reverts the last change of assignments, false when there is none.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(hints) => {
+                self.hints = hints;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cipher text with assigned letters decrypted and `_` for the others.
+    pub fn plain_text(&self) -> String {
+        self.text
+            .chars()
+            .map(|ch| match self.plain_of(ch) {
+                Some(plain) => plain,
+                None if ch == ' ' => ' ',
+                None => '_',
+            })
+            .collect()
+    }
+
+    /// Vocabulary words every cipher word can still be, most frequent first.
+    pub fn candidates(&self) -> Vec<(&str, Vec<WordId>)> {
+        let taken: Vec<u8> = self.hints.iter().map(|hint| hint.plain.0).collect();
+        self.text
+            .split(' ')
+            .filter(|word| !word.is_empty())
+            .map(|cipher_word| {
+                let mut variables: Vec<char> = Vec::new();
+                let pattern: String = cipher_word
+                    .chars()
+                    .map(|ch| match self.plain_of(ch) {
+                        Some(plain) => plain,
+                        None => {
+                            let variable = match variables.iter().position(|&known| known == ch) {
+                                Some(variable) => variable,
+                                None => {
+                                    variables.push(ch);
+                                    variables.len() - 1
+                                }
+                            };
+                            (b'A' + variable as u8) as char
+                        }
+                    })
+                    .collect();
+                let vocabulary = self.index.vocabulary();
+                let words = match pattern.parse::<Pattern>() {
+                    Ok(pattern) => self.index
+                        .query(&pattern)
+                        .into_iter()
+                        .filter(|word_id| {
+                            let word = vocabulary.get(*word_id).unwrap();
+                            cipher_word
+                                .chars()
+                                .zip(word.bytes())
+                                .all(|(ch, plain)| self.plain_of(ch).is_some() || !taken.contains(&plain))
+                        })
+                        .collect(),
+                    Err(_) => Vec::new(),
+                };
+                (cipher_word, words)
+            })
+            .collect()
+    }
+
+    /// Completes the assignments with the best ranked decryption which agrees
+    /// with them. Only the first `SOLVE_LIMIT` solutions of the search are ranked.
+    pub fn solve(&mut self) -> Result<Decryption, SessionError> {
+        let cipher_text = self.cipher_text();
+        let vocabulary = self.index.vocabulary().clone();
+        let found = if cipher_text.conditions().is_empty() {
+            decryption::decryptions(&cipher_text, &vocabulary, &PartialSolution::unconstrained(), 1)
+        } else {
+            let mut solutions_found = 0;
+            let mut solution = Decipher::new(self.index.clone(), &cipher_text).find_solution_with(
+                |_| Flow::Continue,
+                |_| {
+                    solutions_found += 1;
+                    if solutions_found < SOLVE_LIMIT {
+                        Flow::Continue
+                    } else {
+                        Flow::Stop
+                    }
+                },
+            );
+            solution.rank(&vocabulary);
+            solution
+                .partial_solutions()
+                .iter()
+                .flat_map(|partial_solution| decryption::decryptions(&cipher_text, &vocabulary, partial_solution, 1))
+                .take(1)
+                .collect()
+        };
+        let found = found.into_iter().next().ok_or(SessionError::NoSolution)?;
+
+        let mut hints = Vec::new();
+        let mut seen: Vec<char> = Vec::new();
+        for ch in self.text.chars().filter(char::is_ascii_lowercase) {
+            if !seen.contains(&ch) {
+                seen.push(ch);
+                hints.push(Hint {
+                    cipher: ch,
+                    plain: Char::from(found.plain_of(ch).unwrap()),
+                });
+            }
+        }
+        self.replace_hints(hints);
+        Ok(found)
+    }
+
+    fn plain_of(&self, cipher: char) -> Option<char> {
+        self.hints
+            .iter()
+            .find(|hint| hint.cipher == cipher)
+            .map(|hint| hint.plain.0 as char)
+    }
+
+    fn replace_hints(&mut self, hints: Vec<Hint>) {
+        let previous = ::std::mem::replace(&mut self.hints, hints);
+        self.history.push(previous);
+    }
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SessionError::NotInText(cipher) => write!(f, "{} is not in the cipher text", cipher),
+            SessionError::PlainTaken { plain, cipher } => write!(f, "{} is already assigned to {}", plain, cipher),
+            SessionError::NotAssigned(cipher) => write!(f, "{} is not assigned", cipher),
+            SessionError::NoSolution => write!(f, "no solution agrees with the assigned letters"),
+            SessionError::InvalidText(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vocabulary::Vocabulary;
+
+    fn session(text: &str) -> Session {
+        let vocabulary = Vocabulary::new("the\ncat\ndog\nhat".to_string());
+        let mut session = Session::new(Arc::new(VocabularyIndex::new(Arc::new(vocabulary))));
+        session.load(text).unwrap();
+        session
+    }
+
+    fn hint(text: &str) -> Hint {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn loads_normalized_text() {
+        let mut session = session("XLI, gex!");
+        assert_eq!(session.text(), "xli gex");
+        session.assign(hint("x=t")).unwrap();
+        session.load("abc").unwrap();
+        assert_eq!(session.text(), "abc");
+        assert!(session.hints().is_empty());
+        assert!(!session.undo());
+    }
+
+    #[test]
+    fn keeps_the_text_when_loading_an_invalid_one() {
+        let mut session = session("xli gex");
+        let long = "a".repeat(cipher_text::MAX_WORD_LENGTH + 1);
+        assert!(matches!(session.load(&long), Err(SessionError::InvalidText(CipherTextError::WordTooLong(_)))));
+        let many = vec!["ab"; cipher_text::MAX_WORDS + 1].join(" ");
+        assert_eq!(
+            session.load(&many),
+            Err(SessionError::InvalidText(CipherTextError::TooManyWords(cipher_text::MAX_WORDS + 1)))
+        );
+        assert_eq!(session.text(), "xli gex");
+    }
+
+    #[test]
+    fn assigns_letters() {
+        let mut session = session("xli gex");
+        session.assign(hint("x=t")).unwrap();
+        session.assign(hint("l=h")).unwrap();
+        assert_eq!(session.plain_text(), "th_ __t");
+        session.assign(hint("l=a")).unwrap();
+        assert_eq!(session.plain_text(), "ta_ __t");
+        assert_eq!(session.assign(hint("q=e")), Err(SessionError::NotInText('q')));
+        assert_eq!(
+            session.assign(hint("g=t")),
+            Err(SessionError::PlainTaken { plain: 't', cipher: 'x' })
+        );
+        assert_eq!(session.plain_text(), "ta_ __t");
+    }
+
+    #[test]
+    fn unassigns_and_undoes() {
+        let mut session = session("xli gex");
+        session.assign(hint("x=t")).unwrap();
+        session.assign(hint("l=h")).unwrap();
+        session.unassign('x').unwrap();
+        assert_eq!(session.plain_text(), "_h_ ___");
+        assert_eq!(session.unassign('x'), Err(SessionError::NotAssigned('x')));
+
+        assert!(session.undo());
+        assert_eq!(session.plain_text(), "th_ __t");
+        assert!(session.undo());
+        assert_eq!(session.plain_text(), "t__ __t");
+        assert!(session.undo());
+        assert_eq!(session.plain_text(), "___ ___");
+        assert!(!session.undo());
+    }
+
+    #[test]
+    fn lists_candidates_agreeing_with_assignments() {
+        let mut session = session("xli gex");
+        session.assign(hint("x=t")).unwrap();
+        let vocabulary = session.index.vocabulary().clone();
+        let candidates: Vec<(&str, Vec<&str>)> = session
+            .candidates()
+            .into_iter()
+            .map(|(cipher_word, words)| {
+                let mut words: Vec<&str> = words.iter().map(|word_id| vocabulary.get(*word_id).unwrap()).collect();
+                words.sort();
+                (cipher_word, words)
+            })
+            .collect();
+        assert_eq!(candidates, vec![("xli", vec!["the"]), ("gex", vec!["cat", "hat"])]);
+    }
+
+    #[test]
+    fn solves_the_remaining_letters() {
+        let mut session = session("xli gex");
+        session.solve().unwrap();
+        assert_eq!(session.plain_text(), "the cat");
+        assert!(session.undo());
+        assert_eq!(session.plain_text(), "___ ___");
+
+        session.assign(hint("x=d")).unwrap();
+        assert_eq!(session.solve().map(|_| ()), Err(SessionError::NoSolution));
+        assert_eq!(session.plain_text(), "d__ __d");
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use cipher_text::CipherText;
+use decipher::{Decipher, Solution, Step};
+use json::Json;
+use vocabulary_index::VocabularyIndex;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    /// Indented line per event.
+    Log,
+    /// One JSON object per event and line, so that traces of two runs can be
+    /// compared with `diff`.
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Plain `letter` was tried for `cipher_char`. `candidates` has the number
+    /// of candidate words of every constrained cipher word by its index, or is
+    /// `None` when the letter was rejected. `solution` is set when the letter
+    /// completed a partial solution.
+    Tried {
+        depth: usize,
+        cipher_char: char,
+        letter: char,
+        candidates: Option<Vec<(usize, usize)>>,
+        solution: bool,
+    },
+    Backtracked { depth: usize },
+}
+
+/// Solves `cipher_text` sequentially writing every step of the search to
+/// `output` as it is made, so a trace never has to fit in memory. The search
+/// runs to the end even if writing fails, the first write error is returned.
+pub fn trace<W: Write>(
+    index: Arc<VocabularyIndex>,
+    cipher_text: &CipherText,
+    format: TraceFormat,
+    mut output: W,
+) -> io::Result<Solution> {
+    let max_depth = cipher_text.conditions().len();
+    let mut written = Ok(());
+    let solution = Decipher::new(index, cipher_text).find_solution_traced(|step| {
+        if written.is_err() {
+            return;
+        }
+        let event = Event::of(step, max_depth);
+        written = match format {
+            TraceFormat::Log => write!(output, "{}", event),
+            TraceFormat::Json => writeln!(output, "{}", event.json()),
+        };
+    });
+    written?;
+    output.flush()?;
+    Ok(solution)
+}
+
+impl Event {
+    fn of(step: &Step, max_depth: usize) -> Event {
+        match *step {
+            Step::Tried {
+                depth,
+                condition,
+                letter,
+                candidates,
+            } => Event::Tried {
+                depth,
+                cipher_char: condition.cipher_char(),
+                letter: letter.0 as char,
+                candidates: candidates.map(|candidates| {
+                    let mut sizes: Vec<(usize, usize)> = candidates
+                        .satisfactory_words()
+                        .iter()
+                        .map(|(cipher_word_id, words)| (cipher_word_id.index(), words.len()))
+                        .collect();
+                    sizes.sort();
+                    sizes
+                }),
+                solution: candidates.is_some() && depth + 1 == max_depth,
+            },
+            Step::Backtracked { depth } => Event::Backtracked { depth },
+        }
+    }
+
+    pub fn json(&self) -> Json {
+        match *self {
+            Event::Tried {
+                depth,
+                cipher_char,
+                letter,
+                ref candidates,
+                solution,
+            } => Json::object(vec![
+                ("event", "try".into()),
+                ("depth", depth.into()),
+                ("cipher", cipher_char.into()),
+                ("letter", letter.into()),
+                ("solution", solution.into()),
+                (
+                    "candidates",
+                    candidates
+                        .as_ref()
+                        .map(|candidates| {
+                            candidates
+                                .iter()
+                                .map(|&(word, count)| Json::object(vec![("word", word.into()), ("count", count.into())]))
+                                .collect::<Vec<Json>>()
+                        })
+                        .into(),
+                ),
+            ]),
+            Event::Backtracked { depth } => Json::object(vec![("event", "backtrack".into()), ("depth", depth.into())]),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<TraceFormat, String> {
+        match format {
+            "log" => Ok(TraceFormat::Log),
+            "json" => Ok(TraceFormat::Json),
+            _ => Err(format!("unknown trace format {}, expected log or json", format)),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Event::Tried {
+                depth,
+                cipher_char,
+                letter,
+                ref candidates,
+                solution,
+            } => {
+                write!(f, "{:indent$}{}) {} -> {}:", "", depth + 1, cipher_char, letter, indent = depth * 2)?;
+                match *candidates {
+                    Some(ref candidates) => {
+                        for &(word, count) in candidates {
+                            write!(f, " {}[{}]", word, count)?;
+                        }
+                        if solution {
+                            write!(f, " solution")?;
+                        }
+                    }
+                    None => write!(f, " rejected")?,
+                }
+                writeln!(f)
+            }
+            Event::Backtracked { depth } => writeln!(f, "{:indent$}{}) backtrack", "", depth + 1, indent = depth * 2),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use cipher_text::{self, CipherText, Hint};
+use decipher::{Decipher, Flow, PartialSolution};
+use decryption::{self, Decryption};
+use vocabulary::Char;
+use vocabulary_index::VocabularyIndex;
+
+#[derive(Debug)]
+pub enum Uniqueness {
+    NoSolution,
+    Unique(Decryption),
+    Ambiguous(Decryption, Decryption),
+    /// The search stopped after `decipher::MAX_SOLUTIONS` partial solutions
+    /// before finding two decryptions, nothing is known for sure.
+    Truncated,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GenerateError {
+    PlainTextMismatch,
+    NotInVocabulary,
+    SearchTruncated,
+}
+
+/// Searches for decryptions of `cipher_text` until a second one is found.
+pub fn verify(index: Arc<VocabularyIndex>, cipher_text: &CipherText) -> Uniqueness {
+    let vocabulary = index.vocabulary().clone();
+    let mut found: Vec<Decryption> = Vec::with_capacity(2);
+    let mut complete = true;
+    if cipher_text.conditions().is_empty() {
+        found = decryption::decryptions(cipher_text, &vocabulary, &PartialSolution::unconstrained(), 2);
+    } else {
+        let solution = Decipher::new(index, cipher_text).find_solution_with(
+            |_| Flow::Continue,
+            |partial_solution| {
+                let limit = 2 - found.len();
+                found.extend(decryption::decryptions(cipher_text, &vocabulary, partial_solution, limit));
+                if found.len() >= 2 {
+                    Flow::Stop
+                } else {
+                    Flow::Continue
+                }
+            },
+        );
+        complete = solution.is_complete();
+    }
+
+    let mut found = found.into_iter();
+    match (found.next(), found.next()) {
+        (Some(first), Some(second)) => Uniqueness::Ambiguous(first, second),
+        _ if !complete => Uniqueness::Truncated,
+        (Some(only), None) => Uniqueness::Unique(only),
+        _ => Uniqueness::NoSolution,
+    }
+}
+
+/// Adds hints taken from `plain_text` to hints of `cipher_text` until
+/// `plain_text` is its only decryption. Every round hints the most frequent
+/// cipher letter which another decryption reads differently.
+pub fn generate_hints(
+    index: Arc<VocabularyIndex>,
+    cipher_text: &CipherText,
+    plain_text: &str,
+) -> Result<Vec<Hint>, GenerateError> {
+    let plain_text = cipher_text::normalize(plain_text);
+    let plain_by_cipher = align(cipher_text.text(), &plain_text).ok_or(GenerateError::PlainTextMismatch)?;
+    let mut occurrences: HashMap<char, usize> = HashMap::new();
+    for ch in cipher_text.text().chars().filter(char::is_ascii_lowercase) {
+        *occurrences.entry(ch).or_insert(0) += 1;
+    }
+
+    let mut hints = cipher_text.hints().to_vec();
+    loop {
+        let hinted = CipherText::with_hints(cipher_text.text().to_string(), hints.clone());
+        let wrong = match verify(index.clone(), &hinted) {
+            Uniqueness::NoSolution => return Err(GenerateError::NotInVocabulary),
+            Uniqueness::Truncated => return Err(GenerateError::SearchTruncated),
+            Uniqueness::Unique(ref only) if agrees(only, &plain_by_cipher) => return Ok(hints),
+            Uniqueness::Unique(only) => only,
+            Uniqueness::Ambiguous(first, second) => {
+                if agrees(&first, &plain_by_cipher) {
+                    second
+                } else {
+                    first
+                }
+            }
+        };
+        let cipher = plain_by_cipher
+            .iter()
+            .filter(|&(cipher, plain)| wrong.plain_of(*cipher) != Some(*plain))
+            .max_by_key(|&(cipher, _)| (occurrences[cipher], ::std::cmp::Reverse(*cipher)))
+            .map(|(cipher, _)| *cipher)
+            .expect("a wrong decryption differs from the plain text in some letter");
+        hints.push(Hint {
+            cipher,
+            plain: Char::from(plain_by_cipher[&cipher]),
+        });
+    }
+}
+
+/// Pairs cipher letters with plain letters, fails unless every cipher letter
+/// always stands for the same plain letter and no two stand for one.
+fn align(cipher_text: &str, plain_text: &str) -> Option<HashMap<char, char>> {
+    if cipher_text.len() != plain_text.len() {
+        return None;
+    }
+    let mut plain_by_cipher = HashMap::new();
+    let mut cipher_by_plain = HashMap::new();
+    for (cipher, plain) in cipher_text.chars().zip(plain_text.chars()) {
+        if cipher == ' ' || plain == ' ' {
+            if cipher != plain {
+                return None;
+            }
+            continue;
+        }
+        if *plain_by_cipher.entry(cipher).or_insert(plain) != plain
+            || *cipher_by_plain.entry(plain).or_insert(cipher) != cipher
+        {
+            return None;
+        }
+    }
+    Some(plain_by_cipher)
+}
+
+fn agrees(decryption: &Decryption, plain_by_cipher: &HashMap<char, char>) -> bool {
+    plain_by_cipher
+        .iter()
+        .all(|(cipher, plain)| decryption.plain_of(*cipher) == Some(*plain))
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            GenerateError::PlainTextMismatch => write!(f, "plain text does not match the cipher text"),
+            GenerateError::NotInVocabulary => write!(f, "plain text is not a decryption with this vocabulary"),
+            GenerateError::SearchTruncated => write!(f, "too many partial solutions to check uniqueness"),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vocabulary::Vocabulary;
+
+    fn index(words: &str) -> Arc<VocabularyIndex> {
+        Arc::new(VocabularyIndex::new(Arc::new(Vocabulary::new(words.to_string()))))
+    }
+
+    fn hint(text: &str) -> Hint {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_a_unique_decryption() {
+        let index = index("the\ncat\ndog");
+        match verify(index.clone(), &CipherText::new("xli gex".to_string())) {
+            Uniqueness::Unique(only) => assert_eq!(only.text(index.vocabulary()), "the cat"),
+            other => panic!("expected a unique decryption, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn finds_two_decryptions() {
+        let index = index("the\ncat\nbat");
+        match verify(index.clone(), &CipherText::new("xli gex".to_string())) {
+            Uniqueness::Ambiguous(first, second) => {
+                let mut texts = vec![first.text(index.vocabulary()), second.text(index.vocabulary())];
+                texts.sort();
+                assert_eq!(texts, vec!["the bat", "the cat"]);
+            }
+            other => panic!("expected two decryptions, got {:?}", other),
+        }
+        let hinted = CipherText::try_with_hints("xli gex".to_string(), vec![hint("g=c")]).unwrap();
+        assert!(matches!(verify(index, &hinted), Uniqueness::Unique(_)));
+    }
+
+    #[test]
+    fn finds_no_decryption() {
+        let index = index("the\ndog");
+        assert!(matches!(
+            verify(index.clone(), &CipherText::new("xli gex".to_string())),
+            Uniqueness::NoSolution
+        ));
+        // Without conditions every word is checked on its own.
+        assert!(matches!(verify(index.clone(), &CipherText::new("abcd".to_string())), Uniqueness::NoSolution));
+        assert!(matches!(verify(index, &CipherText::new("abc".to_string())), Uniqueness::Ambiguous(_, _)));
+    }
+
+    #[test]
+    fn hints_letters_until_the_plain_text_is_unique() {
+        let index = index("the\ncat\nbat\nsat\nmat");
+        let cipher_text = CipherText::new("xli gex".to_string());
+        let hints = generate_hints(index.clone(), &cipher_text, "The cat!").unwrap();
+        assert_eq!(hints, vec![hint("g=c")]);
+
+        let hinted = CipherText::try_with_hints("xli gex".to_string(), hints).unwrap();
+        match verify(index.clone(), &hinted) {
+            Uniqueness::Unique(only) => assert_eq!(only.text(index.vocabulary()), "the cat"),
+            other => panic!("expected a unique decryption, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_given_hints() {
+        let index = index("the\ncat\nbat");
+        let cipher_text = CipherText::try_with_hints("xli gex".to_string(), vec![hint("g=c")]).unwrap();
+        assert_eq!(generate_hints(index, &cipher_text, "the cat"), Ok(vec![hint("g=c")]));
+    }
+
+    #[test]
+    fn rejects_plain_texts_which_do_not_fit() {
+        let index = index("the\ncat\nbat");
+        let cipher_text = CipherText::new("xli gex".to_string());
+        assert_eq!(generate_hints(index.clone(), &cipher_text, "the ca"), Err(GenerateError::PlainTextMismatch));
+        assert_eq!(generate_hints(index.clone(), &cipher_text, "thecat x"), Err(GenerateError::PlainTextMismatch));
+        assert_eq!(generate_hints(index.clone(), &cipher_text, "the dog"), Err(GenerateError::PlainTextMismatch));
+        assert_eq!(generate_hints(index, &cipher_text, "the cot"), Err(GenerateError::NotInVocabulary));
+    }
+}
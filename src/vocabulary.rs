@@ -1,38 +1,132 @@
+use std::cmp;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
 use std::iter::Iterator;
 
-pub struct Vocabulary<'r> {
-    all: &'r [&'r str],
-    by_length: Vec<Vec<(WordId, &'r str)>>,
+use regex::Regex;
+
+use cache::{put_bytes, put_u32, put_u64, take_string, take_u32, take_u64};
+
+pub const MAX_SOURCES: usize = 64;
+
+/// Owns all words in one `text` arena, words are addressed by byte ranges in it.
+pub struct Vocabulary {
+    text: String,
+    all: Vec<(usize, usize)>,
+    by_length: Vec<Vec<WordId>>,
+    sources: Vec<Source>,
+    word_sources: Vec<SourceSet>,
+    frequencies: Vec<u64>,
+    /// Highest source weight of every word.
+    weights: Vec<f64>,
+    filter: Filter,
+}
+
+/// Words left out of a vocabulary. A word is dropped when any rule matches it,
+/// the frequency rule only drops words with a known frequency.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub exclude: Option<Regex>,
+    pub blacklist: HashSet<String>,
+    pub min_frequency: Option<u64>,
 }
 
+/// Why a word is kept out of the search, checked in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rejection {
+    Empty,
+    NonAscii,
+    Uppercase,
+    Digits,
+    OtherCharacters,
+}
+
+/// Named word list, its weight raises words it contains in solution ranking.
+#[derive(Debug, Clone)]
+pub struct Source {
+    name: String,
+    weight: f64,
+}
+
+#[derive(Clone, Copy, Default)]
+struct SourceSet(u64);
+
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
 pub struct Char(pub u8);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AlphabetIter {
-    current: u8
+    first: u8,
+    current: u8,
+    last: u8,
+    except: Option<u8>,
 }
 
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
 pub struct Position(pub u8);
 
 #[derive(Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord)]
-pub struct WordId(usize);
+pub struct WordId(pub usize);
 
 impl AlphabetIter {
     pub fn new() -> AlphabetIter {
         AlphabetIter {
-            current: 'a' as u8
+            first: b'a',
+            current: b'a',
+            last: b'z',
+            except: None,
         }
     }
+
+    /// Iterates over a single letter.
+    pub fn only(ch: Char) -> AlphabetIter {
+        AlphabetIter {
+            first: ch.0,
+            current: ch.0,
+            last: ch.0,
+            except: None,
+        }
+    }
+
+    /// Same letters without `ch`.
+    pub fn except(self, ch: Char) -> AlphabetIter {
+        AlphabetIter {
+            except: Some(ch.0),
+            ..self
+        }
+    }
+
+    pub fn yielded(&self) -> u8 {
+        let skipped = self.except.is_some_and(|except| except >= self.first && except < self.current);
+        self.current - self.first - skipped as u8
+    }
+
+    /// Number of letters the iterator yields from the start.
+    pub fn size(&self) -> u8 {
+        let skipped = self.except.is_some_and(|except| except >= self.first && except <= self.last);
+        self.last - self.first + 1 - skipped as u8
+    }
+}
+
+impl Default for AlphabetIter {
+    fn default() -> AlphabetIter {
+        AlphabetIter::new()
+    }
 }
 
 impl Iterator for AlphabetIter {
     type Item = Char;
 
     fn next(&mut self) -> Option<Char> {
-        if self.current > 'z' as u8 {
+        if Some(self.current) == self.except {
+            self.current += 1;
+        }
+        if self.current > self.last {
             None
         } else {
             let ret = Char(self.current);
@@ -42,35 +136,403 @@ impl Iterator for AlphabetIter {
     }
 }
 
-impl<'r> Vocabulary<'r> {
-    pub fn new(words: &'r [&'r str]) -> Vocabulary<'r> {
-        let mut by_length = Vec::new();
-        'words: for (word_index, word) in words.iter().enumerate() {
-            for ch in word.chars() {
-                if ch < 'a' || ch > 'z' {
-                    continue 'words;
-                }
-            }
-            if by_length.len() < word.len() + 1 {
-                for _ in 0..word.len() - by_length.len() + 1 {
-                    by_length.push(Vec::new());
+impl Vocabulary {
+    /// Makes a vocabulary of `text` lines, every line is a word.
+    pub fn new(text: String) -> Vocabulary {
+        Vocabulary::merge(vec![(Source::new(String::new(), 1.), text)])
+    }
+
+    /// Makes a vocabulary of lines of several sources, a word found in more than
+    /// one source is stored once and tagged with all of them. A line is either
+    /// a word or a word followed by its frequency, e.g. `photos 1520`.
+    pub fn merge(sources: Vec<(Source, String)>) -> Vocabulary {
+        Vocabulary::merge_filtered(sources, Filter::default())
+    }
+
+    /// Same as `merge`, but leaves out words rejected by `filter`. The highest
+    /// frequency of a word among sources is compared with the threshold.
+    pub fn merge_filtered(sources: Vec<(Source, String)>, filter: Filter) -> Vocabulary {
+        let sources = sources
+            .iter()
+            .map(|(source, text)| (source.clone(), text.as_bytes()))
+            .collect();
+        Vocabulary::read_filtered(sources, filter).expect("reading from memory can't fail")
+    }
+
+    /// Same as `merge_filtered`, but reads the lines of every source from a
+    /// reader as they are needed, so a source is never held in memory whole.
+    pub fn read_filtered<R: BufRead>(sources: Vec<(Source, R)>, filter: Filter) -> io::Result<Vocabulary> {
+        assert!(sources.len() <= MAX_SOURCES);
+        let mut builder = Builder::new();
+        let mut source_list = Vec::with_capacity(sources.len());
+        let mut line = String::new();
+        for (source_index, (source, mut reader)) in sources.into_iter().enumerate() {
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    break;
                 }
+                let trimmed = line.strip_suffix('\n').unwrap_or(&line);
+                let trimmed = trimmed.strip_suffix('\r').unwrap_or(trimmed);
+                let (word, frequency) = parse_line(trimmed);
+                builder.add(word, SourceSet::single(source_index), frequency);
             }
-            by_length[word.len()].push((WordId(word_index), *word));
+            source_list.push(source);
         }
-        Vocabulary {
-            all: words,
-            by_length,
+        if !filter.is_empty() {
+            builder = builder.filtered(&filter);
         }
+        Ok(builder.build(source_list, filter))
     }
 
     pub fn get(&self, word_id: WordId) -> Option<&str> {
-        self.all.get(word_id.0).map(|r| *r)
+        self.all
+            .get(word_id.0)
+            .map(|&(start, end)| &self.text[start..end])
     }
 
-    pub fn by_length(&self) -> &[Vec<(WordId, &str)>] {
+    pub fn by_length(&self) -> &[Vec<WordId>] {
         &self.by_length
     }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    pub fn sources_of(&self, word_id: WordId) -> Vec<&Source> {
+        let set = self.word_sources[word_id.0];
+        self.sources
+            .iter()
+            .enumerate()
+            .filter(|&(source_index, _)| set.contains(source_index))
+            .map(|(_, source)| source)
+            .collect()
+    }
+
+    /// Frequency given next to the word in its source, if any.
+    pub fn frequency(&self, word_id: WordId) -> Option<u64> {
+        match self.frequencies[word_id.0] {
+            0 => None,
+            frequency => Some(frequency),
+        }
+    }
+
+    /// The highest weight among sources of the word.
+    pub fn weight(&self, word_id: WordId) -> f64 {
+        self.weights[word_id.0]
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.sources.len() as u32);
+        for source in &self.sources {
+            put_bytes(out, source.name.as_bytes());
+            put_u64(out, source.weight.to_bits());
+        }
+        put_u64(out, self.all.len() as u64);
+        for (word_index, &(start, end)) in self.all.iter().enumerate() {
+            put_bytes(out, &self.text.as_bytes()[start..end]);
+            put_u64(out, self.word_sources[word_index].0);
+            put_u64(out, self.frequencies[word_index]);
+        }
+        self.filter.encode(out);
+    }
+
+    pub(crate) fn decode(input: &mut &[u8]) -> Option<Vocabulary> {
+        let source_count = take_u32(input)? as usize;
+        if source_count > MAX_SOURCES {
+            return None;
+        }
+        let mut sources = Vec::with_capacity(source_count);
+        for _ in 0..source_count {
+            let name = take_string(input)?;
+            let weight = f64::from_bits(take_u64(input)?);
+            sources.push(Source::new(name, weight));
+        }
+        let word_count = take_u64(input)? as usize;
+        let mut builder = Builder::new();
+        for _ in 0..word_count {
+            let word = take_string(input)?;
+            let sources = SourceSet(take_u64(input)?);
+            builder.add(&word, sources, take_u64(input)?);
+        }
+        let filter = Filter::decode(input)?;
+        Some(builder.build(sources, filter))
+    }
+}
+
+impl Source {
+    pub fn new(name: String, weight: f64) -> Source {
+        Source { name, weight }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+impl SourceSet {
+    fn single(source_index: usize) -> SourceSet {
+        SourceSet(1 << source_index)
+    }
+
+    fn contains(self, source_index: usize) -> bool {
+        self.0 & (1 << source_index) != 0
+    }
+}
+
+struct Builder {
+    text: String,
+    all: Vec<(usize, usize)>,
+    by_length: Vec<Vec<WordId>>,
+    word_sources: Vec<SourceSet>,
+    frequencies: Vec<u64>,
+    /// Words added so far by the hash of their text, the text stays in `text`.
+    seen: HashMap<u64, Vec<WordId>>,
+}
+
+impl Builder {
+    fn new() -> Builder {
+        Builder {
+            text: String::new(),
+            all: Vec::new(),
+            by_length: Vec::new(),
+            word_sources: Vec::new(),
+            frequencies: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, word: &str, sources: SourceSet, frequency: u64) {
+        let hash = word_hash(word);
+        let text = &self.text;
+        let all = &self.all;
+        let known = self.seen.get(&hash).and_then(|word_ids| {
+            word_ids.iter().find(|word_id| {
+                let (start, end) = all[word_id.0];
+                &text[start..end] == word
+            })
+        });
+        if let Some(&word_id) = known {
+            self.word_sources[word_id.0].0 |= sources.0;
+            self.frequencies[word_id.0] = cmp::max(self.frequencies[word_id.0], frequency);
+            return;
+        }
+        let word_id = WordId(self.all.len());
+        let start = self.text.len();
+        self.text.push_str(word);
+        self.all.push((start, self.text.len()));
+        self.text.push('\n');
+        self.word_sources.push(sources);
+        self.frequencies.push(frequency);
+        self.seen.entry(hash).or_default().push(word_id);
+
+        if rejection(word).is_some() {
+            return;
+        }
+        if self.by_length.len() < word.len() + 1 {
+            for _ in 0..word.len() - self.by_length.len() + 1 {
+                self.by_length.push(Vec::new());
+            }
+        }
+        self.by_length[word.len()].push(word_id);
+    }
+
+    /// Builder with the words of this one which `filter` accepts.
+    fn filtered(self, filter: &Filter) -> Builder {
+        let mut builder = Builder::new();
+        for (word_index, &(start, end)) in self.all.iter().enumerate() {
+            let word = &self.text[start..end];
+            if filter.accepts(word, self.frequencies[word_index]) {
+                builder.add(word, self.word_sources[word_index], self.frequencies[word_index]);
+            }
+        }
+        builder
+    }
+
+    fn build(self, sources: Vec<Source>, filter: Filter) -> Vocabulary {
+        let weights = self
+            .word_sources
+            .iter()
+            .map(|set| {
+                sources
+                    .iter()
+                    .enumerate()
+                    .filter(|&(source_index, _)| set.contains(source_index))
+                    .map(|(_, source)| source.weight)
+                    .fold(0., f64::max)
+            })
+            .collect();
+        Vocabulary {
+            text: self.text,
+            all: self.all,
+            by_length: self.by_length,
+            sources,
+            word_sources: self.word_sources,
+            frequencies: self.frequencies,
+            weights,
+            filter,
+        }
+    }
+}
+
+impl Filter {
+    /// Words of a blacklist with a word per line, blank lines are skipped.
+    pub fn parse_blacklist(text: &str) -> HashSet<String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_length.is_none()
+            && self.max_length.is_none()
+            && self.exclude.is_none()
+            && self.blacklist.is_empty()
+            && self.min_frequency.is_none()
+    }
+
+    /// `frequency` is 0 when unknown.
+    pub fn accepts(&self, word: &str, frequency: u64) -> bool {
+        self.min_length.is_none_or(|min_length| word.len() >= min_length)
+            && self.max_length.is_none_or(|max_length| word.len() <= max_length)
+            && self.exclude.as_ref().is_none_or(|exclude| !exclude.is_match(word))
+            && !self.blacklist.contains(word)
+            && self.min_frequency.is_none_or(|min_frequency| frequency == 0 || frequency >= min_frequency)
+    }
+
+    /// Lengths and frequency are written as `u64::MAX` when unset, the pattern
+    /// as an empty string.
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_u64(out, self.min_length.map_or(u64::MAX, |length| length as u64));
+        put_u64(out, self.max_length.map_or(u64::MAX, |length| length as u64));
+        put_bytes(out, self.exclude.as_ref().map_or("", Regex::as_str).as_bytes());
+        let mut blacklist: Vec<&String> = self.blacklist.iter().collect();
+        blacklist.sort();
+        put_u64(out, blacklist.len() as u64);
+        for word in blacklist {
+            put_bytes(out, word.as_bytes());
+        }
+        put_u64(out, self.min_frequency.unwrap_or(u64::MAX));
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Filter> {
+        let unset = |value: u64| if value == u64::MAX { None } else { Some(value) };
+        let min_length = unset(take_u64(input)?).map(|length| length as usize);
+        let max_length = unset(take_u64(input)?).map(|length| length as usize);
+        let exclude = match take_string(input)? {
+            ref pattern if pattern.is_empty() => None,
+            pattern => Some(Regex::new(&pattern).ok()?),
+        };
+        let blacklist_length = take_u64(input)?;
+        let mut blacklist = HashSet::new();
+        for _ in 0..blacklist_length {
+            blacklist.insert(take_string(input)?);
+        }
+        Some(Filter {
+            min_length,
+            max_length,
+            exclude,
+            blacklist,
+            min_frequency: unset(take_u64(input)?),
+        })
+    }
+}
+
+/// Compares exclude patterns by their text.
+impl PartialEq for Filter {
+    fn eq(&self, other: &Filter) -> bool {
+        self.min_length == other.min_length
+            && self.max_length == other.max_length
+            && self.exclude.as_ref().map(Regex::as_str) == other.exclude.as_ref().map(Regex::as_str)
+            && self.blacklist == other.blacklist
+            && self.min_frequency == other.min_frequency
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        let mut rules = Vec::new();
+        if let Some(min_length) = self.min_length {
+            rules.push(format!("min length {}", min_length));
+        }
+        if let Some(max_length) = self.max_length {
+            rules.push(format!("max length {}", max_length));
+        }
+        if let Some(ref exclude) = self.exclude {
+            rules.push(format!("exclude /{}/", exclude.as_str()));
+        }
+        if !self.blacklist.is_empty() {
+            rules.push(format!("blacklist of {} words", self.blacklist.len()));
+        }
+        if let Some(min_frequency) = self.min_frequency {
+            rules.push(format!("min frequency {}", min_frequency));
+        }
+        write!(f, "{}", rules.join(", "))
+    }
+}
+
+fn word_hash(word: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    word.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// None when the word is made of letters a-z only and can be searched for.
+pub(crate) fn rejection(word: &str) -> Option<Rejection> {
+    if word.is_empty() {
+        Some(Rejection::Empty)
+    } else if !word.is_ascii() {
+        Some(Rejection::NonAscii)
+    } else if word.bytes().any(|ch| ch.is_ascii_uppercase()) {
+        Some(Rejection::Uppercase)
+    } else if word.bytes().any(|ch| ch.is_ascii_digit()) {
+        Some(Rejection::Digits)
+    } else if word.bytes().any(|ch| !ch.is_ascii_lowercase()) {
+        Some(Rejection::OtherCharacters)
+    } else {
+        None
+    }
+}
+
+/// Splits `word 1520` into the word and its frequency, 0 stands for unknown.
+pub(crate) fn parse_line(line: &str) -> (&str, u64) {
+    let mut parts = line.split_whitespace();
+    if let (Some(word), Some(frequency), None) = (parts.next(), parts.next(), parts.next()) {
+        if let Ok(frequency) = frequency.parse() {
+            return (word, frequency);
+        }
+    }
+    (line, 0)
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let reason = match *self {
+            Rejection::Empty => "empty",
+            Rejection::NonAscii => "non-ASCII characters",
+            Rejection::Uppercase => "uppercase letters",
+            Rejection::Digits => "digits",
+            Rejection::OtherCharacters => "other characters",
+        };
+        write!(f, "{}", reason)
+    }
 }
 
 impl fmt::Debug for WordId {
@@ -91,7 +553,7 @@ impl fmt::Debug for Position {
     }
 }
 
-impl<'r> fmt::Debug for Vocabulary<'r> {
+impl fmt::Debug for Vocabulary {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let mut by_length = String::with_capacity(128);
         for (length, words) in self.by_length.iter().enumerate() {
@@ -99,9 +561,11 @@ impl<'r> fmt::Debug for Vocabulary<'r> {
         }
         write!(
             f,
-            "Vocabulary {{ all: {}, by_length: [{}] }}",
+            "Vocabulary {{ all: {}, sources: {}, by_length: [{}], filter: {} }}",
             self.all.len(),
-            by_length
+            self.sources.len(),
+            by_length,
+            self.filter
         )
     }
 }
@@ -125,3 +589,92 @@ impl From<u8> for Position {
         Position(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> Filter {
+        Filter {
+            min_length: Some(3),
+            max_length: Some(6),
+            exclude: Some(Regex::new("^x").unwrap()),
+            blacklist: Filter::parse_blacklist("cat\n\n  dog \n"),
+            min_frequency: Some(100),
+        }
+    }
+
+    fn words(vocabulary: &Vocabulary) -> Vec<&str> {
+        let mut words: Vec<&str> = vocabulary
+            .by_length()
+            .iter()
+            .flatten()
+            .map(|&word_id| vocabulary.get(word_id).unwrap())
+            .collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn decodes_what_was_encoded() {
+        for filter in [filter(), Filter::default()] {
+            let mut out = Vec::new();
+            filter.encode(&mut out);
+            let mut input = &out[..];
+            assert_eq!(Filter::decode(&mut input), Some(filter));
+            assert!(input.is_empty());
+            assert_eq!(Filter::decode(&mut &out[..out.len() - 1]), None);
+        }
+    }
+
+    #[test]
+    fn accepts_words_passing_every_rule() {
+        let filter = filter();
+        assert!(filter.accepts("horse", 500));
+        assert!(!filter.accepts("ox", 500));
+        assert!(filter.accepts("horses", 500));
+        assert!(!filter.accepts("elephant", 500));
+        assert!(!filter.accepts("xenon", 500));
+        assert!(!filter.accepts("cat", 500));
+        assert!(!filter.accepts("dog", 500));
+        assert!(!filter.accepts("horse", 99));
+        assert!(filter.accepts("horse", 0));
+        assert!(Filter::default().accepts("x", 1));
+    }
+
+    #[test]
+    fn describes_rules() {
+        assert!(Filter::default().is_empty());
+        assert!(!filter().is_empty());
+        assert_eq!(Filter::default().to_string(), "none");
+        assert_eq!(
+            filter().to_string(),
+            "min length 3, max length 6, exclude /^x/, blacklist of 2 words, min frequency 100"
+        );
+    }
+
+    #[test]
+    fn merges_sources_through_the_filter() {
+        let sources = vec![
+            (Source::new("first".to_string(), 1.), "horse 50\nox\ncat\nzebra\n".to_string()),
+            (Source::new("second".to_string(), 1.), "horse 150\nzebra 20\nmoose\n".to_string()),
+        ];
+        let vocabulary = Vocabulary::merge_filtered(sources, filter());
+        assert_eq!(words(&vocabulary), vec!["horse", "moose"]);
+        assert_eq!(vocabulary.filter(), &filter());
+    }
+
+    #[test]
+    fn skips_the_excepted_letter() {
+        let mut letters = AlphabetIter::new().except(Char::from('c'));
+        assert_eq!(letters.size(), 25);
+        let first: Vec<u8> = letters.by_ref().take(3).map(|ch| ch.0).collect();
+        assert_eq!(first, b"abd".to_vec());
+        assert_eq!(letters.yielded(), 3);
+        assert_eq!(letters.count(), 22);
+
+        assert_eq!(AlphabetIter::new().except(Char::from('a')).next().map(|ch| ch.0), Some(b'b'));
+        assert_eq!(AlphabetIter::new().except(Char::from('z')).count(), 25);
+        assert_eq!(AlphabetIter::new().size(), 26);
+    }
+}
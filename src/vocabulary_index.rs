@@ -1,10 +1,15 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap};
 use std::fmt;
+use std::sync::Arc;
 
+use cache::{put_u32, put_u8, take_u32, take_u8};
+use pattern::{Pattern, Slot};
 use vocabulary::{Char, Position, Vocabulary, WordId};
 
 #[derive(Debug)]
 pub struct VocabularyIndex {
+    vocabulary: Arc<Vocabulary>,
     indexes: HashMap<u8, Index>,
 }
 
@@ -26,7 +31,7 @@ struct Index {
 
 impl Words {
     pub fn intersect_with(&mut self, other: &Words) {
-        self.words = self.intersection(&other)
+        self.words = self.intersection(other)
             .map(|w| w.words)
             .unwrap_or_else(|| Vec::with_capacity(0));
     }
@@ -58,6 +63,34 @@ impl Words {
         }
     }
 
+    pub fn union(&self, other: &Words) -> Words {
+        let mut result = Vec::with_capacity(self.words.len() + other.words.len());
+        let mut left_iter = self.words.iter().peekable();
+        let mut right_iter = other.words.iter().peekable();
+
+        loop {
+            let next = match (left_iter.peek(), right_iter.peek()) {
+                (Some(left), Some(right)) if left < right => left_iter.next(),
+                (Some(left), Some(right)) if left > right => right_iter.next(),
+                (Some(_), Some(_)) => {
+                    right_iter.next();
+                    left_iter.next()
+                }
+                (Some(_), None) => left_iter.next(),
+                (None, Some(_)) => right_iter.next(),
+                (None, None) => break,
+            };
+            result.extend(next);
+        }
+
+        Words { words: result }
+    }
+
+    /// Keeps only the words for which `keep` is true.
+    pub fn retain<F: FnMut(&WordId) -> bool>(&mut self, keep: F) {
+        self.words.retain(keep);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.words.is_empty()
     }
@@ -72,13 +105,14 @@ impl Words {
 }
 
 impl VocabularyIndex {
-    pub fn new(vocabulary: &Vocabulary) -> VocabularyIndex {
+    pub fn new(vocabulary: Arc<Vocabulary>) -> VocabularyIndex {
         let mut indexes = HashMap::new();
         for (words_len, words) in vocabulary.by_length().iter().enumerate() {
             assert!(words_len <= 255);
             let words_len = words_len as u8;
             let mut current_word_len_index = Index::new(words_len, words.len());
-            for &(word_id, word) in words {
+            for &word_id in words {
+                let word = vocabulary.get(word_id).unwrap();
                 for (index, ch) in word.char_indices() {
                     current_word_len_index.insert(Key::new(ch, index as u8), word_id);
                 }
@@ -92,7 +126,11 @@ impl VocabularyIndex {
             }
         }
 
-        VocabularyIndex { indexes }
+        VocabularyIndex { vocabulary, indexes }
+    }
+
+    pub fn vocabulary(&self) -> &Arc<Vocabulary> {
+        &self.vocabulary
     }
 
     pub fn get(&self, word_length: u8, ch: Char, position: Position) -> Option<Words> {
@@ -100,6 +138,98 @@ impl VocabularyIndex {
             .get(&word_length)
             .and_then(|index| index.get(Key::new(ch, position)))
     }
+
+    /// Whether `word` is in the vocabulary, looked up letter by letter.
+    pub fn contains(&self, word: &str) -> bool {
+        if word.is_empty() || word.len() > 255 || !word.bytes().all(|ch| ch.is_ascii_lowercase()) {
+            return false;
+        }
+        let mut candidates: Option<Words> = None;
+        for (position, ch) in word.bytes().enumerate() {
+            let words = match self.get(word.len() as u8, Char(ch), Position(position as u8)) {
+                Some(words) => words,
+                None => return false,
+            };
+            candidates = match candidates {
+                None => Some(words),
+                Some(candidates) => match candidates.intersection(&words) {
+                    Some(intersection) => Some(intersection),
+                    None => return false,
+                },
+            };
+        }
+        true
+    }
+
+    /// Finds words matching `pattern`, the most frequent first. Known letters
+    /// and letter classes are looked up in the index, variables are checked
+    /// against every word left after that.
+    pub fn query(&self, pattern: &Pattern) -> Vec<WordId> {
+        let word_length = pattern.len() as u8;
+        let mut candidates: Option<Words> = None;
+        for (position, slot) in pattern.slots().iter().enumerate() {
+            let position = Position(position as u8);
+            let words = match *slot {
+                Slot::Letter(ch) => self.get(word_length, ch, position),
+                Slot::Class(ref letters) => letters
+                    .iter()
+                    .filter_map(|ch| self.get(word_length, *ch, position))
+                    .fold(None, |union: Option<Words>, words| match union {
+                        Some(union) => Some(union.union(&words)),
+                        None => Some(words),
+                    }),
+                Slot::Any | Slot::Variable(_) => continue,
+            };
+            candidates = match (candidates, words) {
+                (_, None) => return Vec::new(),
+                (None, Some(words)) => Some(words),
+                (Some(candidates), Some(words)) => match candidates.intersection(&words) {
+                    Some(intersection) => Some(intersection),
+                    None => return Vec::new(),
+                },
+            };
+        }
+
+        let candidates = match candidates {
+            Some(words) => words.words,
+            None => match self.vocabulary.by_length().get(pattern.len()) {
+                Some(words) => words.clone(),
+                None => return Vec::new(),
+            },
+        };
+        let mut found: Vec<WordId> = candidates
+            .into_iter()
+            .filter(|word_id| pattern.matches(self.vocabulary.get(*word_id).unwrap()))
+            .collect();
+        found.sort_by(|left, right| {
+            let by_frequency = self.vocabulary.frequency(*right).cmp(&self.vocabulary.frequency(*left));
+            let by_weight = self.vocabulary
+                .weight(*right)
+                .partial_cmp(&self.vocabulary.weight(*left))
+                .unwrap_or(Ordering::Equal);
+            by_frequency.then(by_weight)
+        });
+        found
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        let mut word_lengths: Vec<u8> = self.indexes.keys().cloned().collect();
+        word_lengths.sort_unstable();
+        put_u32(out, word_lengths.len() as u32);
+        for word_length in word_lengths {
+            self.indexes[&word_length].encode(out);
+        }
+    }
+
+    pub(crate) fn decode(vocabulary: Arc<Vocabulary>, input: &mut &[u8]) -> Option<VocabularyIndex> {
+        let index_count = take_u32(input)?;
+        let mut indexes = HashMap::with_capacity(index_count as usize);
+        for _ in 0..index_count {
+            let index = Index::decode(input)?;
+            indexes.insert(index.word_length, index);
+        }
+        Some(VocabularyIndex { vocabulary, indexes })
+    }
 }
 
 impl Key {
@@ -121,7 +251,7 @@ impl Index {
 
     fn insert<T: Into<Key>>(&mut self, key: T, word: WordId) {
         let key: Key = key.into();
-        assert!(key.position.0 <= self.word_length - 1);
+        assert!(key.position.0 < self.word_length);
         self.map
             .entry(key)
             .or_insert_with(|| Words { words: Vec::new() })
@@ -131,9 +261,45 @@ impl Index {
 
     fn get<T: Into<Key>>(&self, key: T) -> Option<Words> {
         let key: Key = key.into();
-        assert!(key.position.0 <= self.word_length - 1);
+        assert!(key.position.0 < self.word_length);
         self.map.get(&key).cloned()
     }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut keys: Vec<Key> = self.map.keys().cloned().collect();
+        keys.sort_unstable_by_key(|key| (key.char.0, key.position.0));
+        put_u8(out, self.word_length);
+        put_u32(out, keys.len() as u32);
+        for key in keys {
+            let words = &self.map[&key];
+            put_u8(out, key.char.0);
+            put_u8(out, key.position.0);
+            put_u32(out, words.words.len() as u32);
+            for word in &words.words {
+                put_u32(out, word.0 as u32);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Index> {
+        let word_length = take_u8(input)?;
+        let key_count = take_u32(input)? as usize;
+        let mut index = Index::new(word_length, key_count);
+        for _ in 0..key_count {
+            let ch = take_u8(input)?;
+            let position = take_u8(input)?;
+            if position >= word_length {
+                return None;
+            }
+            let word_count = take_u32(input)? as usize;
+            let mut words = Vec::with_capacity(word_count);
+            for _ in 0..word_count {
+                words.push(WordId(take_u32(input)? as usize));
+            }
+            index.map.insert(Key::new(ch, position), Words { words });
+        }
+        Some(index)
+    }
 }
 
 impl fmt::Debug for Index {
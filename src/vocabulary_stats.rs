@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use analysis::letter_shares;
+use pattern::Pattern;
+use vocabulary::{parse_line, rejection, Filter, Rejection, Source};
+
+/// Examples kept for every kind of rejected or duplicate line.
+const EXAMPLES: usize = 5;
+/// Patterns listed in `ambiguous_patterns`.
+const AMBIGUOUS_PATTERNS: usize = 10;
+
+/// Health report of vocabulary sources, lines are counted the way
+/// `Vocabulary::merge_filtered` reads them.
+#[derive(Debug, Clone)]
+pub struct VocabularyStats {
+    pub sources: Vec<SourceStats>,
+    /// Different words usable by the search.
+    pub accepted: usize,
+    pub rejected: Vec<Rejected>,
+    pub filter: Filter,
+    /// Different words left out by `filter`, with a few of them.
+    pub filtered: usize,
+    pub filtered_examples: Vec<String>,
+    /// Lines repeating an accepted word of an earlier line of any source.
+    pub duplicates: usize,
+    pub duplicate_examples: Vec<String>,
+    /// Number of accepted words of every length.
+    pub by_length: Vec<usize>,
+    /// Letters with their share of all letters of accepted words, most
+    /// frequent first.
+    pub letters: Vec<(char, f64)>,
+    /// Repetition patterns shared by the most accepted words, with a few of them.
+    pub ambiguous_patterns: Vec<(Pattern, usize, Vec<String>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceStats {
+    pub name: String,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rejected {
+    pub reason: Rejection,
+    pub lines: usize,
+    pub examples: Vec<String>,
+}
+
+pub fn check(sources: &[(Source, String)], filter: Filter) -> VocabularyStats {
+    let mut seen: HashMap<&str, u64> = HashMap::new();
+    let mut accepted: Vec<&str> = Vec::new();
+    let mut rejected: HashMap<Rejection, Rejected> = HashMap::new();
+    let mut duplicates = 0;
+    let mut duplicate_examples = Vec::new();
+    let mut source_stats = Vec::with_capacity(sources.len());
+
+    for (source, text) in sources {
+        let mut lines = 0;
+        for line in text.lines() {
+            lines += 1;
+            let (word, frequency) = parse_line(line);
+            if let Some(reason) = rejection(word) {
+                let rejected = rejected.entry(reason).or_insert_with(|| Rejected {
+                    reason,
+                    lines: 0,
+                    examples: Vec::new(),
+                });
+                rejected.lines += 1;
+                if rejected.examples.len() < EXAMPLES {
+                    rejected.examples.push(word.to_string());
+                }
+            } else if !seen.contains_key(word) {
+                seen.insert(word, frequency);
+                accepted.push(word);
+            } else {
+                let seen_frequency = seen.get_mut(word).unwrap();
+                *seen_frequency = (*seen_frequency).max(frequency);
+                duplicates += 1;
+                if duplicate_examples.len() < EXAMPLES && !duplicate_examples.iter().any(|example| example == word) {
+                    duplicate_examples.push(word.to_string());
+                }
+            }
+        }
+        source_stats.push(SourceStats {
+            name: source.name().to_string(),
+            lines,
+        });
+    }
+
+    // The highest frequency of a word is compared, as in `Vocabulary::merge_filtered`.
+    let (accepted, filtered): (Vec<&str>, Vec<&str>) =
+        accepted.into_iter().partition(|word| filter.accepts(word, seen[word]));
+
+    let mut by_length = Vec::new();
+    let mut by_pattern: HashMap<String, Vec<&str>> = HashMap::new();
+    for word in &accepted {
+        if by_length.len() <= word.len() {
+            by_length.resize(word.len() + 1, 0);
+        }
+        by_length[word.len()] += 1;
+        by_pattern.entry(Pattern::repetition(word).to_string()).or_default().push(word);
+    }
+    let mut ambiguous_patterns: Vec<(String, Vec<&str>)> = by_pattern.into_iter().collect();
+    ambiguous_patterns.sort_by(|left, right| right.1.len().cmp(&left.1.len()).then(left.0.cmp(&right.0)));
+    let mut rejected: Vec<Rejected> = rejected.into_values().collect();
+    rejected.sort_by_key(|rejected| rejected.reason);
+
+    VocabularyStats {
+        sources: source_stats,
+        accepted: accepted.len(),
+        rejected,
+        filter,
+        filtered: filtered.len(),
+        filtered_examples: filtered.iter().take(EXAMPLES).map(|word| word.to_string()).collect(),
+        duplicates,
+        duplicate_examples,
+        by_length,
+        letters: letter_shares(accepted.iter().flat_map(|word| word.chars())),
+        ambiguous_patterns: ambiguous_patterns
+            .into_iter()
+            .take(AMBIGUOUS_PATTERNS)
+            .map(|(_, words)| {
+                let examples = words.iter().take(EXAMPLES).map(|word| word.to_string()).collect();
+                (Pattern::repetition(words[0]), words.len(), examples)
+            })
+            .collect(),
+    }
+}
+
+impl VocabularyStats {
+    pub fn rejected_lines(&self) -> usize {
+        self.rejected.iter().map(|rejected| rejected.lines).sum()
+    }
+}
+
+impl fmt::Display for VocabularyStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for source in &self.sources {
+            writeln!(f, "{}: {} lines", source.name, source.lines)?;
+        }
+        let lines: usize = self.sources.iter().map(|source| source.lines).sum();
+        writeln!(f, "Total: {} lines", lines)?;
+        writeln!(f, "Accepted: {} words", self.accepted)?;
+        writeln!(f, "Rejected: {} lines", self.rejected_lines())?;
+        for rejected in &self.rejected {
+            writeln!(
+                f,
+                "    {:<20} {:6}  {}",
+                rejected.reason.to_string(),
+                rejected.lines,
+                rejected.examples.join(" ")
+            )?;
+        }
+        if !self.filter.is_empty() {
+            writeln!(f, "Filtered out: {} words by {}  {}", self.filtered, self.filter, self.filtered_examples.join(" "))?;
+        }
+        writeln!(f, "Duplicates: {} lines  {}", self.duplicates, self.duplicate_examples.join(" "))?;
+
+        writeln!(f, "Words by length:")?;
+        for (length, &count) in self.by_length.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            writeln!(f, "    {:3} {:7}", length, count)?;
+        }
+
+        writeln!(f, "Letter frequencies:")?;
+        for row in self.letters.chunks(6) {
+            write!(f, "   ")?;
+            for &(ch, share) in row {
+                write!(f, " {} {:5.1}%", ch, share * 100.)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Most ambiguous patterns:")?;
+        for &(ref pattern, count, ref examples) in &self.ambiguous_patterns {
+            writeln!(f, "    {:<12} {:7}  {}", pattern.to_string(), count, examples.join(" "))?;
+        }
+        Ok(())
+    }
+}